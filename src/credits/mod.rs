@@ -0,0 +1,278 @@
+use crate::GameState;
+use crate::despawn_screen;
+use crate::load_data;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+const CREDITS_FILE: &str = "data/credits.ron";
+const SCROLL_SPEED: f32 = 30.0;
+const SKIP_SPEED_MULTIPLIER: f32 = 6.0;
+
+pub struct CreditsPlugin;
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Credits), setup_credits)
+            .add_systems(
+                Update,
+                (scroll_credits, credits_input, button_system).run_if(in_state(GameState::Credits)),
+            )
+            .add_systems(OnExit(GameState::Credits), despawn_screen::<OnCreditsScreen>);
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Credits {
+    sections: Vec<CreditsSection>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CreditsSection {
+    title: String,
+    entries: Vec<CreditsEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CreditsEntry {
+    name: String,
+    detail: Option<String>,
+}
+
+#[derive(Component)]
+struct OnCreditsScreen;
+
+// The list that moves up the screen, offset is its distance from the top
+#[derive(Component)]
+struct CreditsScroll {
+    offset: f32,
+}
+
+// A marker component for credits buttons
+#[derive(Component)]
+enum CreditsButtonAction {
+    Back,
+}
+
+fn setup_credits(mut commands: Commands) {
+    println!("Setup credits");
+    let credits = load_data::<Credits>(CREDITS_FILE).unwrap_or_else(|error| {
+        println!("Could not load credits: {}", error);
+        Credits::default()
+    });
+
+    let camera = Camera2d::default();
+
+    commands.spawn((
+        camera,
+        Transform {
+            translation: Vec3::new(640.0 / 2.0, 320.0 / 2.0, 0.0),
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: bevy::camera::ScalingMode::AutoMax {
+                max_width: (640.0),
+                max_height: (320.0),
+            },
+            scale: 1.0,
+            ..OrthographicProjection::default_2d()
+        }),
+        OnCreditsScreen,
+    ));
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+            OnCreditsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        top: Val::Px(320.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(6.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    CreditsScroll { offset: 320.0 },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Credits"),
+                        TextFont {
+                            font_size: 32.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+
+                    if credits.sections.is_empty() {
+                        parent.spawn((
+                            Text::new("Credits could not be loaded"),
+                            TextFont {
+                                font_size: 18.0,
+                                font: Default::default(),
+                                ..default()
+                            },
+                            TextColor::from(Color::BLACK),
+                        ));
+                    }
+
+                    for section in &credits.sections {
+                        parent.spawn((
+                            Node {
+                                margin: UiRect::top(Val::Px(16.0)),
+                                ..default()
+                            },
+                            Text::new(section.title.clone()),
+                            TextFont {
+                                font_size: 24.0,
+                                font: Default::default(),
+                                ..default()
+                            },
+                            TextColor::from(Color::BLACK),
+                        ));
+
+                        for entry in &section.entries {
+                            parent.spawn((
+                                Text::new(entry.name.clone()),
+                                TextFont {
+                                    font_size: 18.0,
+                                    font: Default::default(),
+                                    ..default()
+                                },
+                                TextColor::from(Color::BLACK),
+                            ));
+
+                            if let Some(detail) = &entry.detail {
+                                parent.spawn((
+                                    Text::new(detail.clone()),
+                                    TextFont {
+                                        font_size: 14.0,
+                                        font: Default::default(),
+                                        ..default()
+                                    },
+                                    TextColor::from(Color::srgb(0.4, 0.4, 0.4)),
+                                ));
+                            }
+                        }
+                    }
+                });
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(10.0),
+                        bottom: Val::Px(10.0),
+                        width: Val::Px(100.0),
+                        height: Val::Px(32.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor::all(Color::BLACK),
+                    BackgroundColor(Color::WHITE),
+                    TextColor(Color::BLACK),
+                    CreditsButtonAction::Back,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Back"),
+                        TextFont {
+                            font_size: 18.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+                });
+        });
+}
+
+// Holding Space or Enter fast forwards, once everything scrolled past go back to the menu
+fn scroll_credits(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut scroll_query: Query<(&mut CreditsScroll, &mut Node, &ComputedNode)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let speed = if keyboard_input.any_pressed([KeyCode::Space, KeyCode::Enter]) {
+        SCROLL_SPEED * SKIP_SPEED_MULTIPLIER
+    } else {
+        SCROLL_SPEED
+    };
+
+    for (mut scroll, mut node, computed_node) in &mut scroll_query {
+        scroll.offset -= speed * time.delta_secs();
+        node.top = Val::Px(scroll.offset);
+
+        let list_height = computed_node.size().y * computed_node.inverse_scale_factor();
+        if list_height > 0.0 && scroll.offset < -list_height {
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+fn credits_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            &mut TextColor,
+            &CreditsButtonAction,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, mut border_color, mut text_color, button_action) in
+        &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+
+                match button_action {
+                    CreditsButtonAction::Back => {
+                        next_state.set(GameState::Menu);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+            }
+            Interaction::None => {
+                *color = Color::WHITE.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::BLACK.into();
+            }
+        }
+    }
+}
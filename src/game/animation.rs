@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::game::character::Facing;
+use crate::load_data;
+
+// Every file in assets/data/animations/ that gets loaded, one set of clips per character
+const ANIMATION_SETS: [&str; 4] = ["hero", "enemy", "sentry", "dust"];
+
+pub(crate) struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnimationLibrary>()
+            .add_systems(Startup, load_animation_library)
+            .add_systems(Update, play_sprite_animations.in_set(SpriteAnimationSystems));
+    }
+}
+
+// Lets the game decide when sprites animate, e.g. not while paused
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SpriteAnimationSystems;
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum LoopMode {
+    Repeat,
+    // Play once and stay on the last frame
+    Once,
+}
+
+#[derive(Deserialize)]
+struct ClipDefinition {
+    texture: String,
+    frame_secs: f32,
+    mode: LoopMode,
+    // Used when the character has no facing or it isn't listed in `frames`
+    default: (usize, usize),
+    #[serde(default)]
+    frames: HashMap<Facing, (usize, usize)>,
+}
+
+#[derive(Deserialize)]
+struct AnimationSetDefinition {
+    frame_size: (u32, u32),
+    columns: u32,
+    rows: u32,
+    clips: HashMap<String, ClipDefinition>,
+}
+
+struct AnimationClip {
+    texture: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    frame_secs: f32,
+    mode: LoopMode,
+    default: (usize, usize),
+    frames: HashMap<Facing, (usize, usize)>,
+}
+
+impl AnimationClip {
+    fn frames(&self, facing: Option<Facing>) -> (usize, usize) {
+        facing
+            .and_then(|facing| self.frames.get(&facing).copied())
+            .unwrap_or(self.default)
+    }
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct AnimationLibrary {
+    sets: HashMap<String, HashMap<String, AnimationClip>>,
+}
+
+impl AnimationLibrary {
+    fn clip(&self, set: &str, state: &str) -> Option<&AnimationClip> {
+        self.sets.get(set).and_then(|clips| clips.get(state))
+    }
+}
+
+// Plays the clip for the current state from the entity's animation set,
+// in the row of its `Facing` if it has one
+#[derive(Component)]
+pub(crate) struct AnimationController {
+    set: String,
+    state: String,
+    // What the sprite currently shows, a change restarts from the first frame
+    playing: Option<(String, Option<Facing>)>,
+    timer: Timer,
+    // Holds the current frame, e.g. enemies while the hero dies
+    paused: bool,
+    // A `Once` clip got past its last frame
+    finished: bool,
+}
+
+impl AnimationController {
+    pub fn new(set: &str, state: &str) -> Self {
+        Self {
+            set: set.to_string(),
+            state: state.to_string(),
+            playing: None,
+            timer: Timer::default(),
+            paused: false,
+            finished: false,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &str) {
+        if self.state != state {
+            self.state = state.to_string();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+fn load_animation_library(
+    mut library: ResMut<AnimationLibrary>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    println!("Load animations");
+
+    for set_name in ANIMATION_SETS {
+        let path = format!("data/animations/{}.ron", set_name);
+        let set = match load_data::<AnimationSetDefinition>(&path) {
+            Ok(set) => set,
+            Err(error) => {
+                println!("Could not load animations: {}", error);
+                continue;
+            }
+        };
+
+        let layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::new(set.frame_size.0, set.frame_size.1),
+            set.columns,
+            set.rows,
+            None,
+            None,
+        ));
+
+        let clips = library.sets.entry(set_name.to_string()).or_default();
+        for (clip_name, clip) in set.clips {
+            clips.insert(
+                clip_name,
+                AnimationClip {
+                    texture: asset_server.load::<Image>(clip.texture),
+                    layout: layout.clone(),
+                    frame_secs: clip.frame_secs,
+                    mode: clip.mode,
+                    default: clip.default,
+                    frames: clip.frames,
+                },
+            );
+        }
+    }
+}
+
+fn play_sprite_animations(
+    time: Res<Time>,
+    library: Res<AnimationLibrary>,
+    mut query: Query<(&mut AnimationController, &mut Sprite, Option<&Facing>)>,
+) {
+    for (mut controller, mut sprite, facing) in &mut query {
+        if controller.paused {
+            continue;
+        }
+
+        let facing = facing.copied();
+        let Some(clip) = library.clip(&controller.set, &controller.state) else {
+            continue;
+        };
+        let (first, last) = clip.frames(facing);
+
+        let is_playing = controller
+            .playing
+            .as_ref()
+            .is_some_and(|(state, playing_facing)| {
+                *state == controller.state && *playing_facing == facing
+            });
+        if !is_playing {
+            controller.playing = Some((controller.state.clone(), facing));
+            controller.timer = Timer::from_seconds(clip.frame_secs, TimerMode::Repeating);
+            controller.finished = false;
+            sprite.image = clip.texture.clone();
+            sprite.texture_atlas = Some(TextureAtlas {
+                layout: clip.layout.clone(),
+                index: first,
+            });
+            continue;
+        }
+
+        controller.timer.tick(time.delta());
+        if !controller.timer.just_finished() {
+            continue;
+        }
+
+        let Some(atlas) = &mut sprite.texture_atlas else {
+            continue;
+        };
+        if atlas.index < last {
+            atlas.index += 1;
+        } else {
+            match clip.mode {
+                LoopMode::Repeat => atlas.index = first,
+                LoopMode::Once => controller.finished = true,
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// The 640x320 area the game camera shows, everything that moves has to stay inside
+#[derive(Resource, Debug, Clone, Copy)]
+pub(crate) struct WorldBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        WorldBounds {
+            min: Vec2::ZERO,
+            max: Vec2::new(640.0, 320.0),
+        }
+    }
+}
+
+impl WorldBounds {
+    // Where the center of something this big can go without poking out
+    pub fn inset(&self, half_size: Vec2) -> Rect {
+        Rect::from_corners(self.min + half_size, self.max - half_size)
+    }
+
+    pub fn clamp(&self, position: Vec2, half_size: Vec2) -> Vec2 {
+        let area = self.inset(half_size);
+        position.clamp(area.min, area.max)
+    }
+}
+
+// Half of the 48x64 character frame, the whole sprite stays on screen
+pub(crate) const CHARACTER_HALF_SIZE: Vec2 = Vec2::new(24.0, 32.0);
+
+// Kept inside the world bounds by keep_in_bounds
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct Bounded {
+    pub half_size: Vec2,
+}
+
+impl Default for Bounded {
+    fn default() -> Self {
+        Bounded {
+            half_size: CHARACTER_HALF_SIZE,
+        }
+    }
+}
+
+// Runs after everything that moves characters, the last word on where they end up
+pub(crate) fn keep_in_bounds(
+    bounds: Res<WorldBounds>,
+    mut query: Query<(&mut Transform, &Bounded)>,
+) {
+    for (mut transform, bounded) in &mut query {
+        let position = transform.translation.truncate();
+        let clamped = bounds.clamp(position, bounded.half_size);
+
+        if clamped != position {
+            transform.translation.x = clamped.x;
+            transform.translation.y = clamped.y;
+        }
+    }
+}
+
+// How far a character moved per second this frame, set by whatever moves it
+#[derive(Component, Default, Deref, DerefMut)]
+pub(crate) struct Velocity(pub Vec2);
+
+// The eight directions the character sheets are drawn in
+#[derive(Component, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub(crate) enum Facing {
+    Down,
+    LeftDown,
+    Left,
+    LeftUp,
+    Up,
+    RightUp,
+    Right,
+    RightDown,
+}
+
+impl Facing {
+    // Closest of the eight directions, None while standing still
+    pub fn from_velocity(velocity: Vec2) -> Option<Facing> {
+        if velocity.length_squared() < f32::EPSILON {
+            return None;
+        }
+
+        let octant = (velocity.y.atan2(velocity.x) / FRAC_PI_4).round() as i32;
+        Some(match octant.rem_euclid(8) {
+            0 => Facing::Right,
+            1 => Facing::RightUp,
+            2 => Facing::Up,
+            3 => Facing::LeftUp,
+            4 => Facing::Left,
+            5 => Facing::LeftDown,
+            6 => Facing::Down,
+            _ => Facing::RightDown,
+        })
+    }
+
+    pub fn to_vec2(self) -> Vec2 {
+        match self {
+            Facing::Left => Vec2::new(-1.0, 0.0),
+            Facing::LeftUp => Vec2::new(-1.0, 1.0).normalize(),
+            Facing::LeftDown => Vec2::new(-1.0, -1.0).normalize(),
+            Facing::Right => Vec2::new(1.0, 0.0),
+            Facing::RightUp => Vec2::new(1.0, 1.0).normalize(),
+            Facing::RightDown => Vec2::new(1.0, -1.0).normalize(),
+            Facing::Up => Vec2::new(0.0, 1.0),
+            Facing::Down => Vec2::new(0.0, -1.0),
+        }
+    }
+}
+
+// Characters keep facing the way they last moved
+pub(crate) fn update_facing(mut query: Query<(&Velocity, &mut Facing)>) {
+    for (velocity, mut facing) in &mut query {
+        if let Some(new_facing) = Facing::from_velocity(velocity.0) {
+            facing.set_if_neq(new_facing);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::game::Diamond;
+use crate::game::enemies::Enemy;
+use crate::game::hero::{Dash, Invulnerable, Jump, Player};
+
+// Where the feet are relative to the center of a 48x64 character frame
+pub(crate) const FEET_OFFSET: Vec2 = Vec2::new(0.0, -24.0);
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) enum Shape {
+    Circle { radius: f32 },
+    Aabb { half_width: f32, half_height: f32 },
+}
+
+// Collider as written in data files, the offset is measured from the feet
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) struct ColliderDefinition {
+    pub shape: Shape,
+    #[serde(default)]
+    pub offset: (f32, f32),
+}
+
+// Touching this hurts or, for diamonds, picks it up
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct Hitbox {
+    shape: Shape,
+    // From the entity's translation to the center of the shape
+    offset: Vec2,
+}
+
+// Where an entity can be hit
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct Hurtbox {
+    shape: Shape,
+    offset: Vec2,
+}
+
+impl Hitbox {
+    pub fn new(shape: Shape) -> Self {
+        Hitbox {
+            shape,
+            offset: Vec2::ZERO,
+        }
+    }
+
+    pub fn at_feet(shape: Shape, offset: Vec2) -> Self {
+        Hitbox {
+            shape,
+            offset: FEET_OFFSET + offset,
+        }
+    }
+}
+
+impl From<ColliderDefinition> for Hitbox {
+    fn from(definition: ColliderDefinition) -> Self {
+        Hitbox::at_feet(definition.shape, Vec2::from(definition.offset))
+    }
+}
+
+impl Hurtbox {
+    pub fn at_feet(shape: Shape, offset: Vec2) -> Self {
+        Hurtbox {
+            shape,
+            offset: FEET_OFFSET + offset,
+        }
+    }
+}
+
+// An enemy touched the hero
+#[derive(Message)]
+pub(crate) struct HeroHit {
+    pub enemy: Entity,
+}
+
+#[derive(Message)]
+pub(crate) struct DiamondCollected {
+    pub diamond: Entity,
+}
+
+fn overlaps(a: Shape, a_center: Vec2, b: Shape, b_center: Vec2) -> bool {
+    match (a, b) {
+        (Shape::Circle { radius: a_radius }, Shape::Circle { radius: b_radius }) => {
+            a_center.distance_squared(b_center) < (a_radius + b_radius).powi(2)
+        }
+        (
+            Shape::Aabb {
+                half_width: a_half_width,
+                half_height: a_half_height,
+            },
+            Shape::Aabb {
+                half_width: b_half_width,
+                half_height: b_half_height,
+            },
+        ) => {
+            (a_center.x - b_center.x).abs() < a_half_width + b_half_width
+                && (a_center.y - b_center.y).abs() < a_half_height + b_half_height
+        }
+        (
+            Shape::Circle { radius },
+            Shape::Aabb {
+                half_width,
+                half_height,
+            },
+        ) => {
+            // Closest point of the box to the circle's center
+            let half_size = Vec2::new(half_width, half_height);
+            let closest = a_center.clamp(b_center - half_size, b_center + half_size);
+            a_center.distance_squared(closest) < radius * radius
+        }
+        (Shape::Aabb { .. }, Shape::Circle { .. }) => overlaps(b, b_center, a, a_center),
+    }
+}
+
+fn hits(
+    hitbox: &Hitbox,
+    hitbox_transform: &Transform,
+    hurtbox: &Hurtbox,
+    hurtbox_transform: &Transform,
+) -> bool {
+    overlaps(
+        hitbox.shape,
+        hitbox_transform.translation.truncate() + hitbox.offset,
+        hurtbox.shape,
+        hurtbox_transform.translation.truncate() + hurtbox.offset,
+    )
+}
+
+pub(crate) fn detect_hero_hits(
+    hero_query: Query<(&Transform, &Hurtbox, &Jump, &Dash, Has<Invulnerable>), With<Player>>,
+    enemy_query: Query<(Entity, &Transform, &Hitbox), With<Enemy>>,
+    mut hero_hits: MessageWriter<HeroHit>,
+) {
+    let Ok((hero_transform, hurtbox, jump, dash, invulnerable)) = hero_query.single() else {
+        return;
+    };
+
+    // Enemies can't touch the hero mid-air, right after a dash started or after a hit
+    if jump.is_airborne() || dash.is_invulnerable() || invulnerable {
+        return;
+    }
+
+    for (enemy, enemy_transform, hitbox) in &enemy_query {
+        if hits(hitbox, enemy_transform, hurtbox, hero_transform) {
+            hero_hits.write(HeroHit { enemy });
+        }
+    }
+}
+
+pub(crate) fn detect_diamond_pickups(
+    hero_query: Query<(&Transform, &Hurtbox), With<Player>>,
+    diamond_query: Query<(Entity, &Transform, &Hitbox), With<Diamond>>,
+    mut diamonds_collected: MessageWriter<DiamondCollected>,
+) {
+    let Ok((hero_transform, hurtbox)) = hero_query.single() else {
+        return;
+    };
+
+    for (diamond, diamond_transform, hitbox) in &diamond_query {
+        if hits(hitbox, diamond_transform, hurtbox, hero_transform) {
+            diamonds_collected.write(DiamondCollected { diamond });
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::game::OnGameScreen;
+use crate::game::animation::AnimationController;
+use crate::game::character::Facing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DustKind {
+    Dash,
+    Jump,
+}
+
+impl DustKind {
+    // Name of the clip in assets/data/animations/dust.ron
+    pub fn clip(self) -> &'static str {
+        match self {
+            DustKind::Dash => "dash",
+            DustKind::Jump => "jump",
+        }
+    }
+}
+
+// Ask for a puff of dust at the given spot, facing the way the hero moves
+#[derive(Message)]
+pub(crate) struct SpawnDust {
+    pub kind: DustKind,
+    pub position: Vec3,
+    pub facing: Facing,
+}
+
+// Removed once its animation has played through
+#[derive(Component)]
+pub(crate) struct Effect;
+
+pub(crate) fn spawn_dust(mut commands: Commands, mut dust_events: MessageReader<SpawnDust>) {
+    for event in dust_events.read() {
+        commands.spawn((
+            Sprite::default(),
+            // Just behind the hero so the puff doesn't cover its feet
+            Transform::from_translation(event.position - Vec3::Z * 0.05),
+            event.facing,
+            AnimationController::new("dust", event.kind.clip()),
+            Effect,
+            OnGameScreen,
+        ));
+    }
+}
+
+pub(crate) fn despawn_finished_effects(
+    mut commands: Commands,
+    query: Query<(Entity, &AnimationController), With<Effect>>,
+) {
+    for (entity, controller) in &query {
+        if controller.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,288 +1,156 @@
 use bevy::prelude::*;
-use rand::Rng;
-use crate::game::AnimationIndices;
-use crate::game::AnimationData;
+use crate::game::level::Level;
+use crate::game::OnGameScreen;
+use crate::game::animation::AnimationController;
+use crate::game::character::{Bounded, Facing, Velocity, WorldBounds};
+use crate::game::collision::{ColliderDefinition, Hitbox};
+use crate::game::enemy_ai::{EnemyBehavior, EnemyState, Teleporter};
+use crate::load_data;
+use serde::Deserialize;
 
 #[derive(Component)]
 pub(crate) struct Enemy;
 
 #[derive(Component)]
 pub(crate) struct EnemyMovement {
-    direction: Vec2,
-    speed: f32,
+    pub direction: Vec2,
+    pub speed: f32,
 }
 
-#[derive(Component, Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub(crate) enum EnemyDirection {
-    Left,
-    LeftUp,
-    LeftDown,
-    Right,
-    RightUp,
-    RightDown,
-    Up,
-    Down,
-    None,
+const ENEMIES_FILE: &str = "data/enemies.ron";
+
+// One kind of enemy as described in assets/data/enemies.ron
+#[derive(Deserialize, Clone)]
+pub(crate) struct EnemyArchetype {
+    pub name: String,
+    // Animation set in assets/data/animations/
+    pub animations: String,
+    pub speed: f32,
+    pub hitbox: ColliderDefinition,
+    #[serde(default)]
+    pub behavior: EnemyBehavior,
+    // Blinks next to the hero this often while chasing
+    #[serde(default)]
+    pub teleport_secs: Option<f32>,
+    // Awarded when the hero shakes off a chase
+    pub score: usize,
 }
 
-// New component to remember previous direction per enemy
-#[derive(Component, Deref, DerefMut)]
-pub(crate) struct PreviousEnemyDirection(pub EnemyDirection);
+#[derive(Resource, Default)]
+pub(crate) struct EnemyArchetypes(pub Vec<EnemyArchetype>);
 
-
-#[derive(Component, Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub(crate) enum EnemyAnimationState {
-    Walk,
+impl EnemyArchetypes {
+    pub fn get(&self, name: &str) -> Option<&EnemyArchetype> {
+        self.0.iter().find(|archetype| archetype.name == name)
+    }
 }
 
-#[derive(Resource)]
-pub(crate) struct EnemyAnimationData {
-    walk: AnimationData,
+// What kind of enemy this is, kept around for logging and scoring
+#[derive(Component)]
+pub(crate) struct EnemyKind {
+    pub name: String,
+    pub score: usize,
 }
 
-impl EnemyAnimationData {
-    pub fn new(
-        walk: AnimationData,
-    ) -> Self {
-        Self {
-            walk,
-        }
-    }
-}
+pub(crate) fn load_enemy_archetypes(mut commands: Commands) {
+    let archetypes = load_data::<Vec<EnemyArchetype>>(ENEMIES_FILE).unwrap_or_else(|error| {
+        println!("Could not load enemies: {}", error);
+        Vec::new()
+    });
 
-#[derive(Component, Deref, DerefMut)]
-pub(crate) struct AnimationTimer(Timer);
+    commands.insert_resource(EnemyArchetypes(archetypes));
+}
 
-pub(crate) fn setup_enemies(
-    mut commands: Commands,
-    animation_data: Res<EnemyAnimationData>
+pub(crate) fn spawn_enemy(
+    commands: &mut Commands,
+    archetype: &EnemyArchetype,
+    position: Vec2,
+    level: &Level,
 ) {
-    println!("Setup enemies");
-    let initial_enemies_count = 1;//10;
-    let enemy_speed = 50.0;
-    let mut random_gen = rand::rng();
-
-    // Hero spawn position (middle of screen)
-    let hero_spawn_x = 320.0;
-    let hero_spawn_y = 160.0;
-    let safe_zone_radius = 100.0; // Adjust this to control exclusion area size
-
-
-    for _ in 0..initial_enemies_count {
-
-        let mut x_pos;
-        let mut y_pos;
-        // Keep generating random positions until one is outside the safe zone
-        // For now, we should find something more determnistic later
-        loop {
-            x_pos = random_gen.random_range(10..630) as f32;
-            y_pos = random_gen.random_range(10..310) as f32;
-            
-            // Calculate distance from hero spawn point
-            let distance = ((x_pos - hero_spawn_x).powi(2) + (y_pos - hero_spawn_y).powi(2)).sqrt();
-            
-            // Accept position if it's outside the safe zone
-            if distance > safe_zone_radius {
-                break;
-            }
-        }
-
-        let direction = if random_gen.random_bool(0.5) {
-            1.0
-        } else {
-            -1.0
-        };
-
-        commands.spawn((
-            Sprite {
-                image: animation_data.walk.texture.clone(),
-                texture_atlas: Some(TextureAtlas {
-                    layout: animation_data.walk.texture_atlas.clone(),
-                    index: 0,
-                }),
-                ..default()
-            },
-            Transform::from_xyz(x_pos as f32, y_pos as f32, 0.0).with_scale(Vec3::splat(1.0)),
-            Enemy,
-            EnemyMovement {
-                direction: Vec2::new(direction, direction),
-                speed: enemy_speed,
-            },
-            EnemyAnimationState::Walk,
-            EnemyDirection::Down,
-            PreviousEnemyDirection(EnemyDirection::Down),
-            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating))
-        ));
+    let mut enemy = commands.spawn((
+        Sprite::default(),
+        Transform::from_translation(position.extend(0.0)),
+        Enemy,
+        EnemyKind {
+            name: archetype.name.clone(),
+            score: archetype.score,
+        },
+        Hitbox::from(archetype.hitbox),
+        EnemyMovement {
+            // Picked by the AI on the first frame
+            direction: Vec2::ZERO,
+            speed: archetype.speed * level.enemy_speed_multiplier(),
+        },
+        EnemyState::wander(&archetype.behavior),
+        archetype.behavior.clone(),
+        Facing::Down,
+        Velocity::default(),
+        Bounded::default(),
+        AnimationController::new(&archetype.animations, "idle"),
+        OnGameScreen,
+    ));
+
+    if let Some(teleport_secs) = archetype.teleport_secs {
+        enemy.insert(Teleporter::new(teleport_secs));
     }
 }
 
 pub(crate) fn enemies_movement(
-    mut query: Query<(&mut Transform, &mut EnemyMovement, &mut EnemyAnimationState, &mut EnemyDirection)>,
-    window_query: Query<&Window, With<Window>>,
+    mut query: Query<(
+        &mut Transform,
+        &mut EnemyMovement,
+        &mut Velocity,
+        &EnemyState,
+        &EnemyBehavior,
+        &Bounded,
+    )>,
+    bounds: Res<WorldBounds>,
     time: Res<Time>,
 ) {
     //println!("Move enemies");
-    let window = window_query.single().unwrap();
-    let window_width = window.width();
-    let window_height = window.height();
-
-    // Calculate the boundaries of the playable area
-    let x_min = 0.0;
-    let x_max = window_width;
-    let y_min = 0.0;
-    let y_max = window_height;
-
-    for (mut transform, mut enemy_movement, mut animation_state, mut enemy_direction) in &mut query {
-        let mut translation = transform.translation;
+    for (mut transform, mut enemy_movement, mut velocity, state, behavior, bounded) in &mut query {
+        let area = bounds.inset(bounded.half_size);
+        let speed = enemy_movement.speed * state.speed_multiplier(behavior);
 
         // Update position based on current direction and speed
-        translation.x += enemy_movement.direction.x * enemy_movement.speed * time.delta_secs();
-        translation.y += enemy_movement.direction.y * enemy_movement.speed * time.delta_secs();
-
-        // Check for collision with horizontal window edges
-        if translation.x > x_max || translation.x < x_min {
-            enemy_movement.direction.x *= -1.0; // Reverse horizontal direction
+        let mut position = transform.translation.truncate()
+            + enemy_movement.direction * speed * time.delta_secs();
+
+        // Mirror the overshoot back inside and point the direction away from the edge,
+        // flipping the sign instead would let an enemy that is still outside flip back and forth
+        if position.x > area.max.x {
+            position.x = 2.0 * area.max.x - position.x;
+            enemy_movement.direction.x = -enemy_movement.direction.x.abs();
+        } else if position.x < area.min.x {
+            position.x = 2.0 * area.min.x - position.x;
+            enemy_movement.direction.x = enemy_movement.direction.x.abs();
         }
 
-        // Check for collision with vertical window edges
-        if translation.y > y_max || translation.y < y_min {
-            enemy_movement.direction.y *= -1.0; // Reverse vertical direction
+        if position.y > area.max.y {
+            position.y = 2.0 * area.max.y - position.y;
+            enemy_movement.direction.y = -enemy_movement.direction.y.abs();
+        } else if position.y < area.min.y {
+            position.y = 2.0 * area.min.y - position.y;
+            enemy_movement.direction.y = enemy_movement.direction.y.abs();
         }
 
-        // Apply the new translation
-        transform.translation = translation;
-
-        *enemy_direction = if enemy_movement.direction.x > 0.0 && enemy_movement.direction.y > 0.0 {
-            EnemyDirection::RightUp
-        } else if enemy_movement.direction.x > 0.0 && enemy_movement.direction.y < 0.0 {
-            EnemyDirection::RightDown
-        } else if enemy_movement.direction.x < 0.0 && enemy_movement.direction.y > 0.0 {
-            EnemyDirection::LeftUp
-        } else if enemy_movement.direction.x < 0.0 && enemy_movement.direction.y < 0.0 {
-            EnemyDirection::LeftDown
-        } else if enemy_movement.direction.x > 0.0 {
-            EnemyDirection::Right
-        } else if enemy_movement.direction.x < 0.0 {
-            EnemyDirection::Left
-        } else if enemy_movement.direction.y > 0.0 {
-            EnemyDirection::Up
-        } else if enemy_movement.direction.y < 0.0 {
-            EnemyDirection::Down
-        } else {
-            EnemyDirection::None
-        };
+        // A very long frame could still mirror past the opposite edge
+        let position = position.clamp(area.min, area.max);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        velocity.0 = enemy_movement.direction * speed;
     }
 }
 
+// Standing enemies show their idle clip, moving ones walk
 pub(crate) fn update_enemy_animation(
-    mut query: Query<(
-        &EnemyAnimationState,
-        &EnemyDirection,
-        &mut Sprite,
-        &mut AnimationTimer,
-        &mut PreviousEnemyDirection,
-    )>,
-    player_animation_data: Res<EnemyAnimationData>, // Access the animation data
-) {
-    //println!("Update player animation");
-    for (current_state, enemy_direction, mut sprite, mut animation_timer, mut prev_direction) in &mut query {
-        //Change animation data if the state has changed
-        //let new_texture_handle = match *current_state {
-        //     EnemyAnimationState::Walk => player_animation_data.walk.texture.clone(),
-        // };
-
-        // let new_atlas_layout_handle = match *current_state {
-        //     EnemyAnimationState::Walk => player_animation_data.walk.texture_atlas.clone(),
-        // };
-println!("Enemy direction: {:?}", enemy_direction);
-        let new_animation_indices = match enemy_direction {
-            EnemyDirection::Down => player_animation_data.walk.frames.clone(),
-            EnemyDirection::Up => AnimationIndices {
-                first: 24,
-                last: 31,
-            },
-            EnemyDirection::Left => AnimationIndices { first: 8, last: 15 },
-            EnemyDirection::Right => AnimationIndices {
-                first: 40,
-                last: 47,
-            },
-            EnemyDirection::LeftUp => AnimationIndices {
-                first: 16,
-                last: 23,
-            },
-            EnemyDirection::RightUp => AnimationIndices {
-                first: 32,
-                last: 39,
-            },
-            EnemyDirection::LeftDown => AnimationIndices { first: 8, last: 15 },
-            EnemyDirection::RightDown => AnimationIndices {
-                first: 40,
-                last: 47,
-            },
-            _ => player_animation_data.walk.frames.clone(), // Default to walk frames
-        };
-
-        // Only change/reset animation if the direction actually changed
-        if prev_direction.0 != *enemy_direction {
-            // update atlas index to the first frame for the new direction
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = new_animation_indices.first;
-            }
-            animation_timer.reset(); // restart the frame timer for the new animation
-            prev_direction.0 = *enemy_direction; // store the current direction
-        }
-    }
-}
-
-pub(crate) fn animate_sprite(
-    time: Res<Time>,
-    mut query: Query<(
-        &mut AnimationTimer,
-        &mut Sprite,
-        &EnemyAnimationState,
-        &EnemyDirection,
-    )>,
-    enemy_animation_data: Res<EnemyAnimationData>,
+    mut query: Query<(&Velocity, &mut AnimationController), With<Enemy>>,
 ) {
-    //println!("Animate sprites");
-    for (mut timer, mut sprite, animation_state, player_direction) in &mut query {
-        timer.tick(time.delta());
-
-        // Determine correct indices for current animation and direction
-        let indices = match *animation_state {
-            EnemyAnimationState::Walk => match player_direction {
-                EnemyDirection::Down => AnimationIndices::new(0, 7),
-                EnemyDirection::Up => AnimationIndices {first: 24, last: 31},
-                EnemyDirection::Left => AnimationIndices { first: 8, last: 15 },
-                EnemyDirection::Right => AnimationIndices {
-                    first: 16,
-                    last: 23,
-                },
-                EnemyDirection::LeftUp => AnimationIndices {
-                    first: 32,
-                    last: 39,
-                },
-                EnemyDirection::LeftDown => AnimationIndices { first: 8, last: 15 },
-                EnemyDirection::RightUp => AnimationIndices {
-                    first: 16,
-                    last: 23,
-                },
-                EnemyDirection::RightDown => AnimationIndices {
-                    first: 16,
-                    last: 23,
-                },
-                _ => enemy_animation_data.walk.frames.clone(),
-            },
-        };
-
-        if timer.just_finished() {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = if atlas.index >= indices.last {
-                    indices.first
-                } else {
-                    atlas.index + 1
-                };
-            }
+    for (velocity, mut controller) in &mut query {
+        if velocity.0 == Vec2::ZERO {
+            controller.set_state("idle");
+        } else {
+            controller.set_state("walk");
         }
     }
 }
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::game::Score;
+use crate::game::character::{Bounded, WorldBounds};
+use crate::game::collision::FEET_OFFSET;
+use crate::game::enemies::{Enemy, EnemyKind, EnemyMovement};
+use crate::game::hero::Player;
+use crate::game::navigation::Navigation;
+use crate::game::rng::GameRng;
+use crate::game::tilemap::{FOOTPRINT_HALF_SIZE, TileMap};
+
+// How an enemy hunts the hero, every enemy carries its own tuning
+#[derive(Component, Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct EnemyBehavior {
+    // The hero gets noticed when closer than this
+    pub sight_radius: f32,
+    // A chase turns into a search once the hero is further away than this
+    pub lose_sight_radius: f32,
+    // Short pause between noticing the hero and going after it, gives the player a tell
+    pub notice_secs: f32,
+    // How long the enemy keeps looking where it last saw the hero
+    pub search_secs: f32,
+    // How often a wandering enemy picks a new direction
+    pub wander_turn_secs: f32,
+    pub chase_speed_multiplier: f32,
+}
+
+impl Default for EnemyBehavior {
+    fn default() -> Self {
+        EnemyBehavior {
+            sight_radius: 120.0,
+            lose_sight_radius: 180.0,
+            notice_secs: 0.4,
+            search_secs: 2.0,
+            wander_turn_secs: 2.0,
+            chase_speed_multiplier: 1.3,
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) enum EnemyState {
+    Wander { timer: Timer },
+    Notice { timer: Timer },
+    Chase,
+    Search { timer: Timer, last_seen: Vec2 },
+}
+
+impl EnemyState {
+    pub fn wander(behavior: &EnemyBehavior) -> Self {
+        EnemyState::Wander {
+            timer: Timer::from_seconds(behavior.wander_turn_secs, TimerMode::Repeating),
+        }
+    }
+
+    // How fast the enemy moves in this state compared to its base speed
+    pub fn speed_multiplier(&self, behavior: &EnemyBehavior) -> f32 {
+        match self {
+            EnemyState::Notice { .. } => 0.0,
+            EnemyState::Chase => behavior.chase_speed_multiplier,
+            EnemyState::Wander { .. } | EnemyState::Search { .. } => 1.0,
+        }
+    }
+}
+
+fn random_direction(random_gen: &mut impl Rng) -> Vec2 {
+    let angle = random_gen.random_range(0.0..std::f32::consts::TAU);
+    Vec2::from_angle(angle)
+}
+
+pub(crate) fn enemy_ai(
+    hero_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &EnemyKind,
+            &EnemyBehavior,
+            &mut EnemyState,
+            &mut EnemyMovement,
+        ),
+        (With<Enemy>, Without<Player>),
+    >,
+    map: Res<TileMap>,
+    mut navigation: ResMut<Navigation>,
+    mut game_rng: ResMut<GameRng>,
+    mut score: ResMut<Score>,
+    time: Res<Time>,
+) {
+    let Ok(hero_transform) = hero_query.single() else {
+        return;
+    };
+    let hero_position = hero_transform.translation.truncate();
+    let hero_feet = hero_position + FEET_OFFSET;
+
+    for (transform, kind, behavior, mut state, mut movement) in &mut enemy_query {
+        let position = transform.translation.truncate();
+        let distance = position.distance(hero_position);
+
+        let next_state = match &mut *state {
+            EnemyState::Wander { timer } => {
+                if distance < behavior.sight_radius {
+                    Some(EnemyState::Notice {
+                        timer: Timer::from_seconds(behavior.notice_secs, TimerMode::Once),
+                    })
+                } else {
+                    timer.tick(time.delta());
+                    if timer.just_finished() || movement.direction == Vec2::ZERO {
+                        movement.direction = random_direction(&mut *game_rng);
+                    }
+                    None
+                }
+            }
+            EnemyState::Notice { timer } => {
+                timer.tick(time.delta());
+                movement.direction = (hero_position - position).normalize_or_zero();
+                timer.is_finished().then_some(EnemyState::Chase)
+            }
+            EnemyState::Chase => {
+                if distance > behavior.lose_sight_radius {
+                    Some(EnemyState::Search {
+                        timer: Timer::from_seconds(behavior.search_secs, TimerMode::Once),
+                        last_seen: hero_position,
+                    })
+                } else {
+                    // Around the walls rather than straight at the hero
+                    movement.direction =
+                        navigation.direction(&map, position + FEET_OFFSET, hero_feet);
+                    None
+                }
+            }
+            EnemyState::Search { timer, last_seen } => {
+                timer.tick(time.delta());
+                movement.direction =
+                    navigation.direction(&map, position + FEET_OFFSET, *last_seen + FEET_OFFSET);
+
+                if distance < behavior.sight_radius {
+                    Some(EnemyState::Chase)
+                } else if timer.is_finished() {
+                    // The hero got away, that's worth something
+                    score.add(kind.score);
+                    Some(EnemyState::wander(behavior))
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(next_state) = next_state {
+            *state = next_state;
+        }
+    }
+}
+
+// Keeps closing the gap by blinking next to the hero while it chases
+#[derive(Component)]
+pub(crate) struct Teleporter {
+    timer: Timer,
+}
+
+impl Teleporter {
+    pub fn new(interval_secs: f32) -> Self {
+        Teleporter {
+            timer: Timer::from_seconds(interval_secs, TimerMode::Repeating),
+        }
+    }
+}
+
+// Close enough to be a threat, far enough to leave a moment to react
+const TELEPORT_DISTANCE: f32 = 70.0;
+const TELEPORT_ATTEMPTS: usize = 8;
+
+pub(crate) fn enemy_teleport(
+    hero_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (&mut Transform, &EnemyState, &Bounded, &mut Teleporter),
+        (With<Enemy>, Without<Player>),
+    >,
+    bounds: Res<WorldBounds>,
+    map: Res<TileMap>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let Ok(hero_transform) = hero_query.single() else {
+        return;
+    };
+    let hero_position = hero_transform.translation.truncate();
+
+    for (mut transform, state, bounded, mut teleporter) in &mut enemy_query {
+        if !matches!(state, EnemyState::Chase) {
+            teleporter.timer.reset();
+            continue;
+        }
+
+        teleporter.timer.tick(time.delta());
+        if !teleporter.timer.just_finished() {
+            continue;
+        }
+
+        // A few spots around the hero, the first one whose footprint is clear of walls.
+        // If none is, the enemy keeps walking until the next interval.
+        let target = (0..TELEPORT_ATTEMPTS)
+            .map(|_| {
+                let target =
+                    hero_position + random_direction(&mut *game_rng) * TELEPORT_DISTANCE;
+                bounds.clamp(target, bounded.half_size)
+            })
+            .find(|&target| {
+                map.push_out(target + FEET_OFFSET, FOOTPRINT_HALF_SIZE) == Vec2::ZERO
+            });
+
+        if let Some(target) = target {
+            transform.translation.x = target.x;
+            transform.translation.y = target.y;
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::game::tilemap::LevelLayout;
+
+// Same size as the authored levels so a generated one fills the screen too
+const WIDTH: usize = 20;
+const HEIGHT: usize = 10;
+const TILE_SIZE: f32 = 32.0;
+
+// Chance of a tile starting out as wall, before the noise gets smoothed into caves
+const WALL_CHANCE: f64 = 0.45;
+const SMOOTHING_STEPS: usize = 4;
+// Too small a cave leaves no room to dodge, try another shape
+const MIN_OPEN_TILES: usize = 80;
+const ATTEMPTS: usize = 20;
+
+// Enemies never spawn fewer steps than this away from the hero
+const MIN_ENEMY_DISTANCE: usize = 6;
+const ENEMY_SPAWNS: usize = 4;
+const DIAMOND_SPAWNS: usize = 24;
+const GRASS_CHANCE: f64 = 0.08;
+
+const NEIGHBOURS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// Rows top first, like the layout strings
+type Grid = Vec<Vec<bool>>;
+
+// Builds a cellular-automata cave from the seed, the same seed always gives the same level
+pub(crate) fn generate_layout(seed: u64) -> LevelLayout {
+    let mut random_gen = StdRng::seed_from_u64(seed);
+
+    for attempt in 0..ATTEMPTS {
+        if let Some(layout) = try_generate(&mut random_gen) {
+            println!("Generated level from seed {} after {} attempts", seed, attempt + 1);
+            return layout;
+        }
+    }
+
+    println!("Could not generate a cave from seed {}, using an open field", seed);
+    open_field()
+}
+
+fn try_generate(random_gen: &mut impl Rng) -> Option<LevelLayout> {
+    let mut solid: Grid = (0..HEIGHT)
+        .map(|row| {
+            (0..WIDTH)
+                .map(|column| is_border(row, column) || random_gen.random_bool(WALL_CHANCE))
+                .collect()
+        })
+        .collect();
+
+    for _ in 0..SMOOTHING_STEPS {
+        solid = smooth(&solid);
+    }
+
+    let hero = nearest_open(&solid, (HEIGHT / 2, WIDTH / 2))?;
+    let distances = flood_fill(&solid, hero);
+
+    // Wall off pockets the hero can't get to, they would only hold unreachable diamonds
+    let mut reachable = Vec::new();
+    for row in 0..HEIGHT {
+        for column in 0..WIDTH {
+            match distances[row][column] {
+                Some(distance) => reachable.push(((row, column), distance)),
+                None => solid[row][column] = true,
+            }
+        }
+    }
+
+    if reachable.len() < MIN_OPEN_TILES {
+        return None;
+    }
+
+    let mut tiles: Vec<Vec<char>> = solid
+        .iter()
+        .map(|line| line.iter().map(|&wall| if wall { '#' } else { '.' }).collect())
+        .collect();
+    tiles[hero.0][hero.1] = 'H';
+
+    let mut far_tiles: Vec<_> = reachable
+        .iter()
+        .filter(|(_, distance)| *distance >= MIN_ENEMY_DISTANCE)
+        .map(|(tile, _)| *tile)
+        .collect();
+    if far_tiles.is_empty() {
+        return None;
+    }
+    far_tiles.shuffle(random_gen);
+    for &(row, column) in far_tiles.iter().take(ENEMY_SPAWNS) {
+        tiles[row][column] = 'E';
+    }
+
+    let mut free_tiles: Vec<_> = reachable
+        .iter()
+        .map(|(tile, _)| *tile)
+        .filter(|&(row, column)| tiles[row][column] == '.')
+        .collect();
+    free_tiles.shuffle(random_gen);
+    for &(row, column) in free_tiles.iter().take(DIAMOND_SPAWNS) {
+        tiles[row][column] = 'D';
+    }
+
+    for &(row, column) in free_tiles.iter().skip(DIAMOND_SPAWNS) {
+        if random_gen.random_bool(GRASS_CHANCE) {
+            tiles[row][column] = ',';
+        }
+    }
+
+    Some(LevelLayout {
+        tile_size: TILE_SIZE,
+        tiles: tiles.into_iter().map(|line| line.into_iter().collect()).collect(),
+    })
+}
+
+fn is_border(row: usize, column: usize) -> bool {
+    row == 0 || column == 0 || row == HEIGHT - 1 || column == WIDTH - 1
+}
+
+// Tiles with many wall neighbours fill in and ones with few open up, that rounds the noise
+// into caves with a few pillars left standing
+fn smooth(solid: &Grid) -> Grid {
+    (0..HEIGHT)
+        .map(|row| {
+            (0..WIDTH)
+                .map(|column| {
+                    if is_border(row, column) {
+                        return true;
+                    }
+
+                    let walls = (row - 1..=row + 1)
+                        .flat_map(|r| (column - 1..=column + 1).map(move |c| (r, c)))
+                        .filter(|&(r, c)| (r, c) != (row, column) && solid[r][c])
+                        .count();
+
+                    match walls {
+                        0..=3 => false,
+                        4 | 5 => solid[row][column],
+                        _ => true,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn nearest_open(solid: &Grid, (row, column): (usize, usize)) -> Option<(usize, usize)> {
+    (0..HEIGHT)
+        .flat_map(|r| (0..WIDTH).map(move |c| (r, c)))
+        .filter(|&(r, c)| !solid[r][c])
+        .min_by_key(|&(r, c)| r.abs_diff(row) + c.abs_diff(column))
+}
+
+// Steps from the start to every tile, None for the ones it can't reach
+fn flood_fill(solid: &Grid, start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut distances = vec![vec![None; WIDTH]; HEIGHT];
+    let mut open = VecDeque::from([start]);
+    distances[start.0][start.1] = Some(0);
+
+    while let Some((row, column)) = open.pop_front() {
+        let distance = distances[row][column].unwrap_or(0);
+
+        for (row_step, column_step) in NEIGHBOURS {
+            let (Some(next_row), Some(next_column)) = (
+                row.checked_add_signed(row_step),
+                column.checked_add_signed(column_step),
+            ) else {
+                continue;
+            };
+            if next_row >= HEIGHT || next_column >= WIDTH {
+                continue;
+            }
+            if solid[next_row][next_column] || distances[next_row][next_column].is_some() {
+                continue;
+            }
+
+            distances[next_row][next_column] = Some(distance + 1);
+            open.push_back((next_row, next_column));
+        }
+    }
+
+    distances
+}
+
+fn open_field() -> LevelLayout {
+    let tiles = (0..HEIGHT)
+        .map(|row| {
+            (0..WIDTH)
+                .map(|column| {
+                    if is_border(row, column) {
+                        '#'
+                    } else if (row, column) == (HEIGHT / 2, WIDTH / 2) {
+                        'H'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    LevelLayout {
+        tile_size: TILE_SIZE,
+        tiles,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile_rows(layout: &LevelLayout) -> Vec<Vec<char>> {
+        layout.tiles.iter().map(|line| line.chars().collect()).collect()
+    }
+
+    fn find(rows: &[Vec<char>], wanted: char) -> Vec<(usize, usize)> {
+        (0..HEIGHT)
+            .flat_map(|row| (0..WIDTH).map(move |column| (row, column)))
+            .filter(|&(row, column)| rows[row][column] == wanted)
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_layout() {
+        for seed in 0..20 {
+            assert_eq!(generate_layout(seed).tiles, generate_layout(seed).tiles);
+        }
+    }
+
+    #[test]
+    fn spawns_are_reachable_and_enemies_keep_their_distance() {
+        for seed in 0..50 {
+            let rows = tile_rows(&generate_layout(seed));
+            assert_eq!(rows.len(), HEIGHT);
+            assert!(rows.iter().all(|line| line.len() == WIDTH));
+
+            let hero = find(&rows, 'H');
+            assert_eq!(hero.len(), 1, "seed {}", seed);
+
+            let solid: Grid = rows
+                .iter()
+                .map(|line| line.iter().map(|&tile| tile == '#').collect())
+                .collect();
+            let distances = flood_fill(&solid, hero[0]);
+
+            for (row, column) in find(&rows, 'D') {
+                assert!(distances[row][column].is_some(), "seed {}", seed);
+            }
+            for (row, column) in find(&rows, 'E') {
+                let distance = distances[row][column];
+                assert!(
+                    distance.is_some_and(|distance| distance >= MIN_ENEMY_DISTANCE),
+                    "seed {}: enemy at {:?} is {:?} steps away",
+                    seed,
+                    (row, column),
+                    distance
+                );
+            }
+        }
+    }
+}
@@ -1,91 +1,409 @@
 use bevy::prelude::*;
-use crate::game::AnimationIndices;
-use crate::game::AnimationData;
+use bevy::sprite::Anchor;
+use crate::GameState;
+use crate::game::OnGameScreen;
+use crate::game::animation::AnimationController;
+use crate::game::character::{Bounded, Facing, Velocity};
+use crate::game::collision::{Hurtbox, Shape};
+use crate::game::effects::{DustKind, SpawnDust};
+use crate::game::enemies::Enemy;
+use crate::game::shadow::Shadow;
+use crate::game::tilemap::TileMap;
+use crate::settings::Settings;
 
 #[derive(Component)]
 pub(crate) struct Player;
 
-#[derive(Component, Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub(crate) enum PlayerDirection {
-    Left,
-    LeftUp,
-    LeftDown,
-    Right,
-    RightUp,
-    RightDown,
-    Up,
-    Down,
-    None,
-}
-
 #[derive(Component, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub(crate) enum PlayerAnimationState {
     Idle,
     Walk,
     Jump,
-    Run,
+    Dash,
+    Dying,
 }
 
+// Shown in place of the regular shadow while the hero dies, same layout as the death sheet
 #[derive(Resource)]
-pub(crate) struct PlayerAnimationData {
-    idle: AnimationData,
-    walk: AnimationData,
-    jump: AnimationData,
-    run: AnimationData,
+pub(crate) struct DeathShadow(pub Handle<Image>);
+
+impl PlayerAnimationState {
+    // Name of the clip in assets/data/animations/hero.ron
+    pub fn clip(self) -> &'static str {
+        match self {
+            PlayerAnimationState::Idle => "idle",
+            PlayerAnimationState::Walk => "walk",
+            PlayerAnimationState::Jump => "jump",
+            PlayerAnimationState::Dash => "dash",
+            PlayerAnimationState::Dying => "death",
+        }
+    }
+}
+
+const JUMP_HEIGHT: f32 = 24.0;
+const JUMP_AIRBORNE_SECS: f32 = 0.5;
+const JUMP_LANDING_SECS: f32 = 0.15;
+// Height of a hero frame, used to turn the jump height into a sprite anchor offset
+const HERO_FRAME_HEIGHT: f32 = 64.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum JumpPhase {
+    Grounded,
+    Airborne,
+    Landing,
+}
+
+// Jump arc of the hero, the Transform stays on the ground and only the sprite is lifted
+#[derive(Component)]
+pub(crate) struct Jump {
+    phase: JumpPhase,
+    timer: Timer,
+    height: f32,
+}
+
+impl Default for Jump {
+    fn default() -> Self {
+        Jump {
+            phase: JumpPhase::Grounded,
+            timer: Timer::from_seconds(JUMP_AIRBORNE_SECS, TimerMode::Once),
+            height: 0.0,
+        }
+    }
+}
+
+impl Jump {
+    pub fn is_airborne(&self) -> bool {
+        self.phase == JumpPhase::Airborne
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.phase != JumpPhase::Grounded
+    }
+
+    // 0.0 on the ground, 1.0 at the top of the arc
+    pub fn height_fraction(&self) -> f32 {
+        self.height / JUMP_HEIGHT
+    }
+
+    // How far through the airborne part of the jump we are
+    pub fn airborne_fraction(&self) -> f32 {
+        if self.is_airborne() {
+            self.timer.fraction()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum DashPhase {
+    Ready,
+    Dashing,
+    Cooldown,
+}
+
+// Short burst in the current direction on Shift, the tuning lives on the component
+#[derive(Component)]
+pub(crate) struct Dash {
+    pub speed: f32,
+    pub duration_secs: f32,
+    pub cooldown_secs: f32,
+    pub invulnerable_secs: f32,
+    phase: DashPhase,
+    direction: Vec2,
+    timer: Timer,
+    invulnerability: Timer,
 }
 
-impl PlayerAnimationData {
+impl Default for Dash {
+    fn default() -> Self {
+        Dash::new(400.0, 0.15, 1.0, 0.25)
+    }
+}
+
+impl Dash {
     pub fn new(
-        idle: AnimationData,
-        walk: AnimationData,
-        jump: AnimationData,
-        run: AnimationData,
+        speed: f32,
+        duration_secs: f32,
+        cooldown_secs: f32,
+        invulnerable_secs: f32,
     ) -> Self {
+        let mut invulnerability = Timer::from_seconds(invulnerable_secs, TimerMode::Once);
+        invulnerability.finish();
+
         Self {
-            idle,
-            walk,
-            jump,
-            run,
+            speed,
+            duration_secs,
+            cooldown_secs,
+            invulnerable_secs,
+            phase: DashPhase::Ready,
+            direction: Vec2::ZERO,
+            timer: Timer::from_seconds(duration_secs, TimerMode::Once),
+            invulnerability,
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.phase == DashPhase::Dashing
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerability.is_finished()
+    }
+
+    // 0.0 when the dash can be used, 1.0 right after it was used
+    pub fn cooldown_fraction(&self) -> f32 {
+        match self.phase {
+            DashPhase::Ready => 0.0,
+            DashPhase::Dashing => 1.0,
+            DashPhase::Cooldown => self.timer.fraction_remaining(),
+        }
+    }
+
+    // Back to ready, keeping the tuning
+    pub fn reset(&mut self) {
+        *self = Dash::new(
+            self.speed,
+            self.duration_secs,
+            self.cooldown_secs,
+            self.invulnerable_secs,
+        );
+    }
+
+    fn start(&mut self, direction: Vec2) {
+        self.phase = DashPhase::Dashing;
+        self.direction = direction;
+        self.timer = Timer::from_seconds(self.duration_secs, TimerMode::Once);
+        self.invulnerability = Timer::from_seconds(self.invulnerable_secs, TimerMode::Once);
+    }
+}
+
+// Eight frames of the death animation plus a short hold on the last one
+const DEATH_SECS: f32 = 1.5;
+
+// The hero got hit, the run ends once the death animation has played out
+#[derive(Component)]
+pub(crate) struct Dying {
+    timer: Timer,
+}
+
+impl Default for Dying {
+    fn default() -> Self {
+        Dying {
+            timer: Timer::from_seconds(DEATH_SECS, TimerMode::Once),
+        }
+    }
+}
+
+// Grace period after losing a life, the hero blinks until it runs out
+const HIT_INVULNERABLE_SECS: f32 = 2.0;
+const HIT_FLASH_SECS: f32 = 0.1;
+const KNOCKBACK_SPEED: f32 = 250.0;
+const KNOCKBACK_SECS: f32 = 0.15;
+
+#[derive(Component)]
+pub(crate) struct Invulnerable {
+    timer: Timer,
+    flash: Timer,
+}
+
+impl Default for Invulnerable {
+    fn default() -> Self {
+        Invulnerable {
+            timer: Timer::from_seconds(HIT_INVULNERABLE_SECS, TimerMode::Once),
+            flash: Timer::from_seconds(HIT_FLASH_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+// Short shove away from whatever hit the hero
+#[derive(Component)]
+pub(crate) struct Knockback {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+impl Knockback {
+    pub fn away_from(from: Vec2, hero: Vec2) -> Self {
+        // Straight down if the enemy sits exactly on the hero
+        let direction = (hero - from).try_normalize().unwrap_or(Vec2::NEG_Y);
+        Knockback {
+            velocity: direction * KNOCKBACK_SPEED,
+            timer: Timer::from_seconds(KNOCKBACK_SECS, TimerMode::Once),
+        }
+    }
+}
+
+pub(crate) fn update_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Sprite), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut sprite) in &mut query {
+        invulnerable.timer.tick(time.delta());
+        invulnerable.flash.tick(time.delta());
+
+        if invulnerable.timer.is_finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if invulnerable.flash.just_finished() {
+            let alpha = if sprite.color.alpha() < 1.0 { 1.0 } else { 0.3 };
+            sprite.color = Color::WHITE.with_alpha(alpha);
+        }
+    }
+}
+
+pub(crate) fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Knockback, &mut Transform), With<Player>>,
+) {
+    for (entity, mut knockback, mut transform) in &mut query {
+        knockback.timer.tick(time.delta());
+        // Fades out over the knockback so it doesn't stop dead
+        let strength = knockback.timer.fraction_remaining();
+        transform.translation += (knockback.velocity * strength * time.delta_secs()).extend(0.0);
+
+        if knockback.timer.is_finished() {
+            commands.entity(entity).remove::<Knockback>();
         }
     }
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub(crate) struct AnimationTimer(Timer);
+// Run condition for everything that should freeze while the hero dies
+pub(crate) fn hero_alive(dying_query: Query<(), (With<Player>, With<Dying>)>) -> bool {
+    dying_query.is_empty()
+}
+
+pub(crate) fn player_death(
+    time: Res<Time>,
+    mut query: Query<&mut Dying, With<Player>>,
+    mut enemy_query: Query<&mut AnimationController, With<Enemy>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for mut dying in &mut query {
+        // Enemies freeze in place while the hero's death plays out
+        for mut controller in &mut enemy_query {
+            if !controller.is_paused() {
+                controller.set_paused(true);
+            }
+        }
+
+        dying.timer.tick(time.delta());
+        if dying.timer.is_finished() {
+            println!("Game Over!");
+            next_state.set(GameState::GameOver);
+        }
+    }
+}
 
-pub(crate) fn setup_hero(mut commands: Commands, animation_data: Res<PlayerAnimationData>) {
+// While dying the shadow plays the matching frame of the death shadow sheet
+pub(crate) fn update_death_shadow(
+    hero_query: Query<(&Sprite, &Children), (With<Player>, With<Dying>)>,
+    mut shadow_query: Query<&mut Sprite, (With<Shadow>, Without<Player>)>,
+    death_shadow: Res<DeathShadow>,
+) {
+    for (hero_sprite, children) in &hero_query {
+        let Some(hero_atlas) = &hero_sprite.texture_atlas else {
+            continue;
+        };
+
+        for &child in children {
+            if let Ok(mut shadow_sprite) = shadow_query.get_mut(child) {
+                shadow_sprite.image = death_shadow.0.clone();
+                shadow_sprite.texture_atlas = Some(hero_atlas.clone());
+            }
+        }
+    }
+}
+
+pub(crate) fn setup_hero(mut commands: Commands, map: Res<TileMap>) {
     println!("Setup hero");
 
     commands.spawn((
-        Sprite {
-            image: animation_data.idle.texture.clone(), // Start with the idle texture
-            texture_atlas: Some(TextureAtlas {
-                layout: animation_data.idle.texture_atlas.clone(),
-                index: animation_data.idle.frames.first,
-            }),
-            ..default()
-        },
-        Transform::from_xyz(640.0 / 2.0, 320.0 / 2.0, 0.0).with_scale(Vec3::splat(1.0)),
+        Sprite::default(),
+        Transform::from_translation(map.character_position(map.hero_spawn()).extend(0.0)),
         Player,
         PlayerAnimationState::Idle,
-        PlayerDirection::None,
-        AnimationTimer(Timer::from_seconds(0.125, TimerMode::Repeating)),
+        Facing::Down,
+        Velocity::default(),
+        Bounded::default(),
+        AnimationController::new("hero", PlayerAnimationState::Idle.clip()),
+        Jump::default(),
+        Dash::default(),
+        Anchor::default(),
+        // The body from the feet up, a bit narrower than the 48px frame
+        Hurtbox::at_feet(
+            Shape::Aabb {
+                half_width: 10.0,
+                half_height: 16.0,
+            },
+            Vec2::new(0.0, 16.0),
+        ),
+        OnGameScreen,
     ));
 }
 
-pub(crate) fn player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+// Put the hero back on the spawn point, e.g. when a new level starts
+pub(crate) fn reset_hero(
+    mut commands: Commands,
     mut query: Query<
         (
+            Entity,
+            &mut Sprite,
             &mut Transform,
             &mut PlayerAnimationState,
-            &mut PlayerDirection,
+            &mut Facing,
+            &mut Velocity,
+            &mut Jump,
+            &mut Dash,
+            &mut Anchor,
         ),
         With<Player>,
     >,
-    time: Res<Time>,
+    map: Res<TileMap>,
+) {
+    for (
+        entity,
+        mut sprite,
+        mut transform,
+        mut animation_state,
+        mut facing,
+        mut velocity,
+        mut jump,
+        mut dash,
+        mut anchor,
+    ) in &mut query
+    {
+        let spawn = map.character_position(map.hero_spawn());
+        transform.translation.x = spawn.x;
+        transform.translation.y = spawn.y;
+        *animation_state = PlayerAnimationState::Idle;
+        *facing = Facing::Down;
+        *velocity = Velocity::default();
+        *jump = Jump::default();
+        dash.reset();
+        *anchor = Anchor::default();
+        sprite.color = Color::WHITE;
+        commands
+            .entity(entity)
+            .remove::<(Invulnerable, Knockback)>();
+    }
+}
+
+// Keys gathered every frame for the next fixed step. Presses stay until a step uses them,
+// so none get lost or counted twice when frames and steps don't line up.
+#[derive(Resource, Default)]
+pub(crate) struct PlayerInput {
+    direction: Vec2,
+    jump: bool,
+    dash: bool,
+}
+
+pub(crate) fn buffer_player_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut player_input: ResMut<PlayerInput>,
 ) {
-    //println!("Move player");
     let mut direction = Vec2::ZERO;
 
     if keyboard_input.pressed(KeyCode::ArrowLeft) {
@@ -104,209 +422,166 @@ pub(crate) fn player_movement(
         direction.y -= 1.0;
     }
 
-    if direction != Vec2::ZERO {
-        direction = direction; //normalize(); // Normalize the direction vector
-    }
-
-    let base_speed = 100.0;
-
-    let speed = if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        base_speed * 1.5 // Running speed
-    } else {
-        base_speed
-    };
+    player_input.direction = direction;
+    player_input.jump |= keyboard_input.just_pressed(KeyCode::Space);
+    player_input.dash |= keyboard_input.just_pressed(KeyCode::ShiftLeft);
+}
 
-    for (mut transform, mut animation_state, mut player_direction) in &mut query {
-        transform.translation += Vec3::new(
-            direction.x * speed * time.delta_secs(),
-            direction.y * speed * time.delta_secs(),
-            0.0,
-        );
+// Space starts a jump, the hero follows an arc and then needs a moment to land
+pub(crate) fn player_jump(
+    mut player_input: ResMut<PlayerInput>,
+    mut query: Query<(&Transform, &Facing, &mut Jump, &mut Anchor), With<Player>>,
+    mut dust_events: MessageWriter<SpawnDust>,
+    time: Res<Time>,
+) {
+    let jump_pressed = std::mem::take(&mut player_input.jump);
 
-        // Update animation state based on movement
-        if direction != Vec2::ZERO {
-            if keyboard_input.pressed(KeyCode::ShiftLeft) {
-                if *animation_state != PlayerAnimationState::Run {
-                    *animation_state = PlayerAnimationState::Run;
-                }
-            } else if keyboard_input.pressed(KeyCode::Space) {
-                if *animation_state != PlayerAnimationState::Jump {
-                    *animation_state = PlayerAnimationState::Jump;
-                }
-            } else {
-                if *animation_state != PlayerAnimationState::Walk {
-                    *animation_state = PlayerAnimationState::Walk;
+    for (transform, facing, mut jump, mut anchor) in &mut query {
+        match jump.phase {
+            JumpPhase::Grounded => {
+                if jump_pressed {
+                    jump.phase = JumpPhase::Airborne;
+                    jump.timer = Timer::from_seconds(JUMP_AIRBORNE_SECS, TimerMode::Once);
+                    dust_events.write(SpawnDust {
+                        kind: DustKind::Jump,
+                        position: transform.translation,
+                        facing: *facing,
+                    });
                 }
             }
+            JumpPhase::Airborne => {
+                jump.timer.tick(time.delta());
+                let progress = jump.timer.fraction();
+                jump.height = 4.0 * JUMP_HEIGHT * progress * (1.0 - progress);
 
-            // direction
-            if keyboard_input.pressed(KeyCode::ArrowLeft)
-                && keyboard_input.pressed(KeyCode::ArrowUp)
-            {
-                *player_direction = PlayerDirection::LeftUp;
-            } else if keyboard_input.pressed(KeyCode::ArrowLeft)
-                && keyboard_input.pressed(KeyCode::ArrowDown)
-            {
-                *player_direction = PlayerDirection::LeftDown;
-            } else if keyboard_input.pressed(KeyCode::ArrowRight)
-                && keyboard_input.pressed(KeyCode::ArrowUp)
-            {
-                *player_direction = PlayerDirection::RightUp;
-            } else if keyboard_input.pressed(KeyCode::ArrowRight)
-                && keyboard_input.pressed(KeyCode::ArrowDown)
-            {
-                *player_direction = PlayerDirection::RightDown;
-            } else if keyboard_input.pressed(KeyCode::ArrowLeft) {
-                *player_direction = PlayerDirection::Left;
-            } else if keyboard_input.pressed(KeyCode::ArrowRight) {
-                *player_direction = PlayerDirection::Right;
-            } else if keyboard_input.pressed(KeyCode::ArrowUp) {
-                *player_direction = PlayerDirection::Up;
-            } else if keyboard_input.pressed(KeyCode::ArrowDown) {
-                *player_direction = PlayerDirection::Down;
-            } else {
-                *player_direction = PlayerDirection::None; // Reset direction if no arrow keys are pressed
+                if jump.timer.is_finished() {
+                    jump.phase = JumpPhase::Landing;
+                    jump.timer = Timer::from_seconds(JUMP_LANDING_SECS, TimerMode::Once);
+                    jump.height = 0.0;
+                    dust_events.write(SpawnDust {
+                        kind: DustKind::Jump,
+                        position: transform.translation,
+                        facing: *facing,
+                    });
+                }
             }
-        } else {
-            if *animation_state != PlayerAnimationState::Idle {
-                *animation_state = PlayerAnimationState::Idle;
+            JumpPhase::Landing => {
+                jump.timer.tick(time.delta());
+                if jump.timer.is_finished() {
+                    jump.phase = JumpPhase::Grounded;
+                }
             }
-
-            *player_direction = PlayerDirection::None; // Reset direction when idle
         }
+
+        anchor.0 = Vec2::new(0.0, -jump.height / HERO_FRAME_HEIGHT);
     }
 }
 
-pub(crate) fn update_player_animation(
-    mut query: Query<(
-        &PlayerAnimationState,
-        &PlayerDirection,
-        &mut Sprite,
-        &mut AnimationTimer,
-    )>,
-    player_animation_data: Res<PlayerAnimationData>, // Access the animation data
+pub(crate) fn player_dash(
+    mut player_input: ResMut<PlayerInput>,
+    mut query: Query<(&mut Transform, &mut Dash, &Facing, &Velocity, &Jump), With<Player>>,
+    mut dust_events: MessageWriter<SpawnDust>,
+    time: Res<Time>,
 ) {
-    //println!("Update player animation");
-    for (current_state, player_direction, mut sprite, mut animation_timer) in &mut query {
-        // Change animation data if the state has changed
-        let new_texture_handle = match *current_state {
-            PlayerAnimationState::Idle => player_animation_data.idle.texture.clone(),
-            PlayerAnimationState::Walk => player_animation_data.walk.texture.clone(),
-            PlayerAnimationState::Jump => player_animation_data.jump.texture.clone(),
-            PlayerAnimationState::Run => player_animation_data.run.texture.clone(),
-        };
+    let dash_pressed = std::mem::take(&mut player_input.dash);
 
-        let new_atlas_layout_handle = match *current_state {
-            PlayerAnimationState::Idle => player_animation_data.idle.texture_atlas.clone(),
-            PlayerAnimationState::Walk => player_animation_data.walk.texture_atlas.clone(),
-            PlayerAnimationState::Jump => player_animation_data.jump.texture_atlas.clone(),
-            PlayerAnimationState::Run => player_animation_data.run.texture_atlas.clone(),
-        };
+    for (mut transform, mut dash, facing, velocity, jump) in &mut query {
+        dash.invulnerability.tick(time.delta());
 
-        let new_animation_indices = match *current_state {
-            PlayerAnimationState::Idle => player_animation_data.idle.frames.clone(),
-            PlayerAnimationState::Walk => {
-                match player_direction {
-                    PlayerDirection::Down => player_animation_data.walk.frames.clone(),
-                    PlayerDirection::Up => AnimationIndices {
-                        first: 24,
-                        last: 31,
-                    },
-                    PlayerDirection::Left => AnimationIndices { first: 8, last: 15 },
-                    PlayerDirection::Right => AnimationIndices {
-                        first: 40,
-                        last: 47,
-                    },
-                    PlayerDirection::LeftUp => AnimationIndices {
-                        first: 16,
-                        last: 23,
-                    },
-                    PlayerDirection::RightUp => AnimationIndices {
-                        first: 32,
-                        last: 39,
-                    },
-                    PlayerDirection::LeftDown => AnimationIndices { first: 8, last: 15 },
-                    PlayerDirection::RightDown => AnimationIndices {
-                        first: 40,
-                        last: 47,
-                    },
-                    _ => player_animation_data.walk.frames.clone(), // Default to walk frames
+        match dash.phase {
+            DashPhase::Ready => {
+                if dash_pressed
+                    && velocity.0 != Vec2::ZERO
+                    && !jump.is_active()
+                {
+                    dash.start(facing.to_vec2());
+                    dust_events.write(SpawnDust {
+                        kind: DustKind::Dash,
+                        position: transform.translation,
+                        facing: *facing,
+                    });
                 }
             }
-            PlayerAnimationState::Jump => player_animation_data.jump.frames.clone(),
-            PlayerAnimationState::Run => player_animation_data.run.frames.clone(),
-        };
+            DashPhase::Dashing => {
+                dash.timer.tick(time.delta());
+                let step = dash.direction * dash.speed * time.delta_secs();
+                transform.translation += step.extend(0.0);
 
-        if sprite.texture_atlas.is_none()
-            || sprite.texture_atlas.as_ref().unwrap().layout != new_atlas_layout_handle
-        {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                // Update the sprite's texture atlas and index
-                atlas.layout = new_atlas_layout_handle;
-                atlas.index = new_animation_indices.first; // Reset to the first frame of the new animation
+                if dash.timer.is_finished() {
+                    dash.phase = DashPhase::Cooldown;
+                    dash.timer = Timer::from_seconds(dash.cooldown_secs, TimerMode::Once);
+                }
+            }
+            DashPhase::Cooldown => {
+                dash.timer.tick(time.delta());
+                if dash.timer.is_finished() {
+                    dash.phase = DashPhase::Ready;
+                }
             }
-
-            sprite.image = new_texture_handle;
-            animation_timer.reset(); // Reset animation timer
         }
     }
 }
 
-pub(crate) fn animate_sprite(
+pub(crate) fn player_movement(
+    player_input: Res<PlayerInput>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut PlayerAnimationState,
+            &mut Velocity,
+            &Jump,
+            &Dash,
+        ),
+        With<Player>,
+    >,
     time: Res<Time>,
-    mut query: Query<(
-        &mut AnimationTimer,
-        &mut Sprite,
-        &PlayerAnimationState,
-        &PlayerDirection,
-    )>,
-    player_animation_data: Res<PlayerAnimationData>,
+    settings: Res<Settings>,
 ) {
-    //println!("Animate sprites");
-    for (mut timer, mut sprite, animation_state, player_direction) in &mut query {
-        timer.tick(time.delta());
-
-        // Determine correct indices for current animation and direction
-        let indices = match *animation_state {
-            PlayerAnimationState::Idle => player_animation_data.idle.frames.clone(),
-            PlayerAnimationState::Walk => match player_direction {
-                PlayerDirection::Down => player_animation_data.walk.frames.clone(),
-                PlayerDirection::Up => AnimationIndices {
-                    first: 24,
-                    last: 31,
-                },
-                PlayerDirection::Left => AnimationIndices { first: 8, last: 15 },
-                PlayerDirection::Right => AnimationIndices {
-                    first: 40,
-                    last: 47,
-                },
-                PlayerDirection::LeftUp => AnimationIndices {
-                    first: 16,
-                    last: 23,
-                },
-                PlayerDirection::LeftDown => AnimationIndices { first: 8, last: 15 },
-                PlayerDirection::RightUp => AnimationIndices {
-                    first: 32,
-                    last: 39,
-                },
-                PlayerDirection::RightDown => AnimationIndices {
-                    first: 40,
-                    last: 47,
-                },
-                _ => player_animation_data.walk.frames.clone(),
-            },
-            PlayerAnimationState::Jump => player_animation_data.jump.frames.clone(),
-            PlayerAnimationState::Run => player_animation_data.run.frames.clone(),
-        };
+    //println!("Move player");
+    let direction = player_input.direction;
 
-        if timer.just_finished() {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = if atlas.index >= indices.last {
-                    indices.first
-                } else {
-                    atlas.index + 1
-                };
+    let speed = 100.0 * settings.movement_speed.multiplier();
+
+    for (mut transform, mut animation_state, mut velocity, jump, dash) in &mut query {
+        if jump.is_active() && *animation_state != PlayerAnimationState::Jump {
+            *animation_state = PlayerAnimationState::Jump;
+        }
+
+        // The dash moves the hero on its own, keep its direction until it ends
+        if dash.is_dashing() {
+            if *animation_state != PlayerAnimationState::Dash {
+                *animation_state = PlayerAnimationState::Dash;
             }
+            velocity.0 = dash.direction * dash.speed;
+            continue;
+        }
+
+        // No steering while landing
+        if jump.phase == JumpPhase::Landing {
+            velocity.0 = Vec2::ZERO;
+            continue;
         }
+
+        velocity.0 = direction * speed;
+        transform.translation += (velocity.0 * time.delta_secs()).extend(0.0);
+
+        // Update animation state based on movement
+        // While jumping keep the jump animation, facing still follows the movement
+        if !jump.is_active() {
+            let new_state = if direction != Vec2::ZERO {
+                PlayerAnimationState::Walk
+            } else {
+                PlayerAnimationState::Idle
+            };
+            animation_state.set_if_neq(new_state);
+        }
+    }
+}
+
+pub(crate) fn update_player_animation(
+    mut query: Query<(&PlayerAnimationState, &mut AnimationController)>,
+) {
+    for (animation_state, mut controller) in &mut query {
+        controller.set_state(animation_state.clip());
     }
 }
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::game::OnGameScreen;
+use crate::InGameState;
+
+#[derive(Resource)]
+pub struct Level {
+    number: usize,
+}
+
+impl Level {
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn advance(&mut self) {
+        self.number += 1;
+    }
+
+    // Every level adds a couple of diamonds to collect
+    pub fn diamond_count(&self) -> usize {
+        10 + (self.number - 1) * 2
+    }
+
+    // Enemies get a bit faster every level, applied on top of their own speed
+    pub fn enemy_speed_multiplier(&self) -> f32 {
+        1.0 + (self.number - 1) as f32 * 0.2
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level { number: 1 }
+    }
+}
+
+// Data files list one entry per level, levels past the end keep using the last one
+pub(crate) fn for_level<'a, T>(entries: &'a [T], level: &Level) -> Option<&'a T> {
+    entries.get(level.number() - 1).or(entries.last())
+}
+
+#[derive(Resource)]
+pub(crate) struct LevelClearedTimer {
+    timer: Timer,
+}
+
+impl Default for LevelClearedTimer {
+    fn default() -> Self {
+        LevelClearedTimer {
+            timer: Timer::from_seconds(2.0, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct OnLevelClearedScreen;
+
+pub(crate) fn setup_level_cleared(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut timer: ResMut<LevelClearedTimer>,
+) {
+    println!("Level {} cleared", level.number());
+    timer.timer.reset();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            OnLevelClearedScreen,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.)),
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.5)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(format!("Level {} cleared", level.number())),
+                        TextFont {
+                            font: Default::default(),
+                            font_size: 40.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+pub(crate) fn level_cleared_countdown(
+    mut timer: ResMut<LevelClearedTimer>,
+    time: Res<Time>,
+    mut level: ResMut<Level>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    timer.timer.tick(time.delta());
+    if timer.timer.is_finished() {
+        level.advance();
+        next_state.set(InGameState::Playing);
+    }
+}
@@ -1,17 +1,61 @@
+mod animation;
+mod character;
+mod collision;
+mod effects;
 mod enemies;
+mod enemy_ai;
+mod generator;
 mod hero;
+mod level;
+mod navigation;
+mod pause;
+mod rng;
 mod score;
+mod shadow;
+mod tilemap;
+mod waves;
 
 use crate::GameState;
+use crate::InGameState;
 use crate::despawn_screen;
+use bevy::ecs::schedule::ScheduleConfigs;
+use bevy::ecs::system::ScheduleSystem;
 use bevy::prelude::*;
-use enemies::{Enemy, enemies_movement, setup_enemies, update_enemy_animation, animate_sprite as enemy_animate_sprite, EnemyAnimationData};
+use animation::{SpriteAnimationPlugin, SpriteAnimationSystems};
+use character::{WorldBounds, keep_in_bounds, update_facing};
+use collision::{
+    DiamondCollected, HeroHit, Hitbox, Shape, detect_diamond_pickups, detect_hero_hits,
+};
+use effects::{SpawnDust, despawn_finished_effects, spawn_dust};
+use enemies::{
+    Enemy, EnemyKind, enemies_movement, load_enemy_archetypes, update_enemy_animation,
+};
+use enemy_ai::{enemy_ai, enemy_teleport};
 use hero::{
-    Player, PlayerAnimationData, animate_sprite, player_movement,
-    setup_hero, update_player_animation,
+    DeathShadow, Dying, Invulnerable, Knockback, Player, PlayerAnimationState, PlayerInput,
+    apply_knockback, buffer_player_input, hero_alive, player_dash, player_death, player_jump,
+    player_movement, reset_hero, setup_hero, update_death_shadow, update_invulnerability,
+    update_player_animation,
+};
+use level::{LevelClearedTimer, OnLevelClearedScreen, level_cleared_countdown, setup_level_cleared};
+use navigation::{Navigation, prune_navigation};
+use pause::{
+    OnPauseScreen, OnResumeScreen, ResumeCountdown, pause_button_system, pause_on_focus_lost,
+    resume_countdown, setup_pause_menu, setup_resume_countdown, toggle_pause,
 };
-use rand::Rng;
-use score::{Score, setup_score_ui, update_score_ui};
+use rand::seq::IndexedRandom;
+use score::{setup_score_ui, update_dash_ui, update_lives_ui, update_score_ui};
+use shadow::{attach_shadows, setup_shadows, update_shadows};
+use tilemap::{LevelTile, TileMap, build_level, collide_with_walls, load_level_layouts};
+use waves::{
+    SpawnTelegraph, WaveDirector, load_wave_schedule, setup_telegraphs, spawn_telegraphed_enemies,
+    start_waves, wave_director,
+};
+
+pub use level::Level;
+pub use rng::{GameRng, RunSeed};
+pub use score::{Lives, Score};
+pub use tilemap::LevelSource;
 
 pub struct GamePlugin;
 
@@ -24,122 +68,192 @@ struct Diamond;
 #[derive(Component)]
 struct Background;
 
-#[derive(Component, Clone)]
-pub(crate) struct AnimationIndices {
-    first: usize,
-    last: usize,
-}
-
-impl AnimationIndices {
-    pub fn new(first: usize, last: usize) -> Self {
-        Self { first, last }
-    }
-}
-
-pub(crate) struct AnimationData {
-    pub texture_atlas: Handle<TextureAtlasLayout>,
-    pub frames: AnimationIndices,
-    pub texture: Handle<Image>,
-}
-
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_plugins(SpriteAnimationPlugin)
+            .add_sub_state::<InGameState>()
             .init_resource::<Score>()
+            .init_resource::<Lives>()
+            .init_resource::<Level>()
+            .init_resource::<LevelClearedTimer>()
+            .init_resource::<ResumeCountdown>()
+            .init_resource::<WaveDirector>()
+            .init_resource::<WorldBounds>()
+            .init_resource::<TileMap>()
+            .init_resource::<LevelSource>()
+            .init_resource::<GameRng>()
+            .init_resource::<RunSeed>()
+            .init_resource::<Navigation>()
+            .init_resource::<PlayerInput>()
+            .add_message::<SpawnDust>()
+            .add_message::<HeroHit>()
+            .add_message::<DiamondCollected>()
+            .configure_sets(
+                Update,
+                SpriteAnimationSystems.run_if(in_state(InGameState::Playing)),
+            )
+            .add_systems(OnEnter(GameState::InGame), setup_run())
+            // Restart from the pause menu re-enters InGame, which OnEnter/OnExit ignore
+            .add_systems(
+                OnTransition {
+                    exited: GameState::InGame,
+                    entered: GameState::InGame,
+                },
+                (despawn_screen::<OnGameScreen>, setup_run()).chain(),
+            )
+            // Keys are read every frame but used by the fixed steps below
             .add_systems(
-                OnEnter(GameState::InGame),
+                RunFixedMainLoop,
+                buffer_player_input
+                    .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop)
+                    .run_if(in_state(InGameState::Playing)),
+            )
+            // Gameplay steps at a fixed rate, the same seed and the same keys replay the same
+            // run however long the frames take
+            .add_systems(
+                FixedUpdate,
                 (
-                    setup_game,
-                    setup_background,
-                    setup_hero.after(setup_game),
-                    setup_enemies.after(setup_game),
-                    setup_diamonds.after(setup_game),
-                    setup_score_ui,
-                ),
+                    (
+                        player_jump,
+                        player_dash.after(player_jump),
+                        player_movement.after(player_dash),
+                        collide_with_walls
+                            .after(player_movement)
+                            .after(apply_knockback)
+                            .after(enemies_movement),
+                        keep_in_bounds.after(collide_with_walls),
+                        detect_hero_hits.after(keep_in_bounds),
+                        hero_hit.after(detect_hero_hits),
+                        update_invulnerability,
+                        apply_knockback.after(player_movement),
+                        detect_diamond_pickups.after(keep_in_bounds),
+                        // After hero_hit, so a hero that just lost its last life is dying
+                        collect_diamonds.after(detect_diamond_pickups).after(hero_hit),
+                        wave_director,
+                        spawn_telegraphed_enemies.after(wave_director),
+                        prune_navigation,
+                        // Both draw from GameRng, a fixed order keeps runs repeatable
+                        enemy_ai.after(prune_navigation).after(wave_director),
+                        enemy_teleport.after(enemy_ai),
+                        enemies_movement.after(enemy_teleport),
+                    )
+                        .run_if(hero_alive),
+                    update_facing.after(player_movement).after(enemies_movement),
+                    player_death,
+                )
+                    .run_if(in_state(InGameState::Playing)),
             )
             .add_systems(
                 Update,
                 (
-                    player_movement,
-                    animate_sprite,
-                    enemy_animate_sprite,
-                    update_player_animation,
-                    update_enemy_animation,
+                    update_enemy_animation
+                        .before(SpriteAnimationSystems)
+                        .run_if(hero_alive),
+                    update_player_animation.before(SpriteAnimationSystems),
+                    attach_shadows,
+                    update_shadows.after(attach_shadows),
+                    update_death_shadow.after(SpriteAnimationSystems),
+                    spawn_dust.before(SpriteAnimationSystems),
+                    despawn_finished_effects.after(SpriteAnimationSystems),
                     update_score_ui,
-                    collision_detection,
-                    collision_detection_diamonds,
-                    enemies_movement,
+                    update_lives_ui,
+                    update_dash_ui,
                 )
-                    .run_if(in_state(GameState::InGame)),
+                    .run_if(in_state(InGameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (toggle_pause, pause_on_focus_lost).run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(OnEnter(InGameState::Paused), setup_pause_menu)
+            .add_systems(
+                Update,
+                pause_button_system.run_if(in_state(InGameState::Paused)),
+            )
+            .add_systems(OnExit(InGameState::Paused), despawn_screen::<OnPauseScreen>)
+            .add_systems(OnEnter(InGameState::Resuming), setup_resume_countdown)
+            .add_systems(
+                Update,
+                resume_countdown.run_if(in_state(InGameState::Resuming)),
+            )
+            .add_systems(OnExit(InGameState::Resuming), despawn_screen::<OnResumeScreen>)
+            .add_systems(OnEnter(InGameState::LevelCleared), setup_level_cleared)
+            .add_systems(
+                Update,
+                level_cleared_countdown.run_if(in_state(InGameState::LevelCleared)),
+            )
+            .add_systems(
+                OnExit(InGameState::LevelCleared),
+                despawn_screen::<OnLevelClearedScreen>,
+            )
+            .add_systems(
+                OnTransition {
+                    exited: InGameState::LevelCleared,
+                    entered: InGameState::Playing,
+                },
+                (
+                    despawn_screen::<Enemy>,
+                    despawn_screen::<SpawnTelegraph>,
+                    despawn_screen::<LevelTile>,
+                    build_level,
+                    reset_hero.after(build_level),
+                    setup_diamonds.after(build_level),
+                    start_waves,
+                ),
             )
             .add_systems(OnExit(GameState::InGame), despawn_screen::<OnGameScreen>);
     }
 }
 
-fn setup_game(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+// Everything that builds a fresh run
+fn setup_run() -> ScheduleConfigs<ScheduleSystem> {
+    (
+        reset_run,
+        (
+            setup_game,
+            setup_background,
+            setup_shadows,
+            load_level_layouts,
+            build_level.after(load_level_layouts),
+            setup_hero.after(setup_game).after(build_level),
+            load_enemy_archetypes,
+            load_wave_schedule,
+            setup_telegraphs,
+            start_waves.after(load_wave_schedule),
+            setup_diamonds.after(setup_game).after(build_level),
+            setup_score_ui,
+        )
+            .after(reset_run),
+    )
+        .into_configs()
+}
+
+// Every run starts from scratch, nothing carries over from the previous one
+fn reset_run(
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut level: ResMut<Level>,
+    mut level_cleared_timer: ResMut<LevelClearedTimer>,
+    mut game_rng: ResMut<GameRng>,
+    mut player_input: ResMut<PlayerInput>,
+    run_seed: Res<RunSeed>,
 ) {
-    println!("Setup game");
+    *game_rng = run_seed.0.map(GameRng::new).unwrap_or_default();
+    *player_input = PlayerInput::default();
+    println!("Reset run, seed {}", game_rng.seed());
+    *score = Score::default();
+    *lives = Lives::default();
+    *level = Level::default();
+    *level_cleared_timer = LevelClearedTimer::default();
+}
 
-    // load enemy texture and atlas
-    let enemy_texture_walk = asset_server.load::<Image>("sprites/characters/enemy/walk/walk.png");
-    let enemy_walk_atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 6, None, None);
-    let enemy_wakl_atlas_handle = texture_atlas_layouts.add(enemy_walk_atlas_layout);
-
-    // Load textures for all animations
-    let texture_idle = asset_server.load::<Image>("sprites/characters/hero/idle/idle.png");
-    let texture_walk = asset_server.load::<Image>("sprites/characters/hero/walk/walk.png");
-    let texture_run = asset_server.load::<Image>("sprites/characters/hero/dash/dash.png");
-    let texture_jump = asset_server.load::<Image>("sprites/characters/hero/jump/normal/jump.png");
-
-    // Create texture atlases for each animation
-    let idle_atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 6, None, None);
-    let walk_atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 6, None, None);
-    let run_atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 6, None, None);
-    let jump_atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 6, None, None);
-
-    let idle_atlas_handle = texture_atlas_layouts.add(idle_atlas_layout);
-    let walk_atlas_handle = texture_atlas_layouts.add(walk_atlas_layout);
-    let run_atlas_handle = texture_atlas_layouts.add(run_atlas_layout);
-    let jump_atlas_handle = texture_atlas_layouts.add(jump_atlas_layout);
-
-    let idle_frames = AnimationIndices::new(0, 7);
-    let walk_frames = AnimationIndices::new(0, 7);
-    let jump_frames = AnimationIndices::new(0, 7);
-    let run_frames = AnimationIndices::new(0, 7);
-
-    commands.insert_resource(PlayerAnimationData::new(
-        AnimationData {
-            texture_atlas: idle_atlas_handle,
-            texture: texture_idle,
-            frames: idle_frames,
-        },
-        AnimationData {
-            texture_atlas: walk_atlas_handle,
-            texture: texture_walk,
-            frames: walk_frames,
-        },
-        AnimationData {
-            texture_atlas: jump_atlas_handle,
-            texture: texture_jump,
-            frames: jump_frames,
-        },
-        AnimationData {
-            texture_atlas: run_atlas_handle,
-            texture: texture_run,
-            frames: run_frames,
-        },
-    ));
+fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
+    println!("Setup game");
 
-    commands.insert_resource(EnemyAnimationData::new(
-        AnimationData {
-            texture_atlas: enemy_wakl_atlas_handle,
-            texture: enemy_texture_walk,
-            frames: AnimationIndices::new(0, 7),
-        },
-    ));
+    let texture_death_shadow =
+        asset_server.load::<Image>("sprites/characters/hero/death_shadow/death_normal.png");
+    commands.insert_resource(DeathShadow(texture_death_shadow));
 
     // Camera
     let camera = Camera2d::default();
@@ -190,74 +304,199 @@ fn setup_diamonds(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    level: Res<Level>,
+    map: Res<TileMap>,
+    mut game_rng: ResMut<GameRng>,
 ) {
-    // this should be per Level
-    let diamond_count = 10;
+    let diamond_count = level.diamond_count();
     let diamond_length = 10.0;
-    let mut random_gen = rand::rng();
 
-    for _x in 0..diamond_count {
-        let x_pos = random_gen.random_range(10..630);
-        let y_pos = random_gen.random_range(10..310);
+    // The layout's diamond spots, or any open tile once a level asks for more than it has
+    let candidates = if map.diamond_spawns().len() >= diamond_count {
+        map.diamond_spawns().to_vec()
+    } else {
+        map.open_tiles()
+            .into_iter()
+            .filter(|&tile| tile != map.hero_spawn())
+            .collect()
+    };
+
+    for &tile in candidates.choose_multiple(&mut *game_rng, diamond_count) {
+        let position = map.tile_center(tile);
 
         commands.spawn((
             Mesh2d(meshes.add(bevy::math::primitives::Cuboid::from_length(diamond_length))),
             MeshMaterial2d(
                 materials.add(ColorMaterial::from_color(Color::srgb(190.0, 190.0, 190.0))),
             ),
-            Transform::from_xyz(x_pos as f32, y_pos as f32, 0.0),
+            Transform::from_translation(position.extend(0.0)),
+            Hitbox::new(Shape::Aabb {
+                half_width: diamond_length / 2.0,
+                half_height: diamond_length / 2.0,
+            }),
             Diamond,
+            OnGameScreen,
         ));
     }
 }
 
-fn collision_detection(
-    enemy_query: Query<&Transform, With<Enemy>>,
-    hero_query: Query<&Transform, With<Player>>,
-    mut next_state: ResMut<NextState<GameState>>,
+fn hero_hit(
+    mut commands: Commands,
+    mut hero_hits: MessageReader<HeroHit>,
+    enemy_query: Query<(&Transform, &EnemyKind)>,
+    mut hero_query: Query<(Entity, &Transform, &mut PlayerAnimationState), With<Player>>,
+    mut lives: ResMut<Lives>,
 ) {
-    //println!("Detect collision");
-    let hero_transform = hero_query.single().unwrap();
+    // Several enemies can touch the hero in the same frame, one is enough
+    let Some(hit) = hero_hits.read().last() else {
+        return;
+    };
+    let Ok((hero_entity, hero_transform, mut animation_state)) = hero_query.single_mut() else {
+        return;
+    };
+    let Ok((enemy_transform, enemy_kind)) = enemy_query.get(hit.enemy) else {
+        return;
+    };
 
-    for enemy_transform in enemy_query.iter() {
-        let distance = hero_transform
-            .translation
-            .distance(enemy_transform.translation);
+    let lives_left = lives.lose();
+    println!("Got hit by {}! {} lives left", enemy_kind.name, lives_left);
 
-        if distance < 30.0 {
-            println!("Got hit: Game Over!");
-            next_state.set(GameState::GameOver);
-        }
-    } // Placeholder for future collision detection logic
+    if lives_left == 0 {
+        *animation_state = PlayerAnimationState::Dying;
+        commands.entity(hero_entity).insert(Dying::default());
+    } else {
+        commands.entity(hero_entity).insert((
+            Invulnerable::default(),
+            Knockback::away_from(
+                enemy_transform.translation.truncate(),
+                hero_transform.translation.truncate(),
+            ),
+        ));
+    }
 }
 
-fn collision_detection_diamonds(
+fn collect_diamonds(
     mut commands: Commands,
-    diamond_query: Query<(Entity, &Transform), With<Diamond>>,
-    hero_query: Query<(&Transform,), With<Player>>,
+    mut diamonds_collected: MessageReader<DiamondCollected>,
+    diamond_query: Query<(), With<Diamond>>,
+    hero_query: Query<Has<Dying>, With<Player>>,
     mut score: ResMut<Score>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut next_state: ResMut<NextState<InGameState>>,
 ) {
-    //println!("Detect collision");
-    let hero_transform = hero_query.single().unwrap();
-
-    for (diamond_entity, diamond_transform) in diamond_query.iter() {
-        let distance = hero_transform
-            .0
-            .translation
-            .distance(diamond_transform.translation);
-
-        if distance < 30.0 {
-            commands.entity(diamond_entity).despawn();
-            // commands.insert_resource(Score {
-            //     value: score.value + 1,
-            // });
-            score.increase();
-        }
+    let mut collected = 0;
+
+    for event in diamonds_collected.read() {
+        commands.entity(event.diamond).despawn();
+        score.increase();
+        collected += 1;
     }
 
-    let diamonds_remaining = diamond_query.iter().count();
-    //println!("Diamonds left; {}", diamonds_remaining);
+    // Despawns are deferred, so the ones collected this frame are still in the query
+    let diamonds_remaining = diamond_query.iter().count() - collected;
+
+    // The last diamond doesn't count once the last life is gone, the run ends instead
+    let dying = hero_query.iter().any(|dying| dying);
 
     // if player collects all diamonds, move to next Level
+    if collected > 0 && diamonds_remaining == 0 && !dying {
+        next_state.set(InGameState::LevelCleared);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::collision::FEET_OFFSET;
+    use crate::settings::Settings;
+    use bevy::state::app::StatesPlugin;
+    use bevy::state::state::StateTransition;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::window::PrimaryWindow;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            StatesPlugin,
+            bevy::input::InputPlugin,
+        ))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .insert_resource(Settings::default())
+        // One fixed step per frame, after the first frame which has no time yet
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        .init_state::<GameState>()
+        .add_plugins(GamePlugin);
+
+        app.world_mut().spawn((Window::default(), PrimaryWindow));
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        app.world_mut().run_schedule(StateTransition);
+        app
+    }
+
+    #[test]
+    fn last_life_and_last_diamond_in_the_same_step_ends_the_run() {
+        let mut app = test_app();
+
+        let hero_position = app
+            .world_mut()
+            .query_filtered::<&Transform, With<Player>>()
+            .single(app.world())
+            .unwrap()
+            .translation;
+
+        // One diamond left, right under the hero's feet
+        let diamonds: Vec<Entity> = app
+            .world_mut()
+            .query_filtered::<Entity, With<Diamond>>()
+            .iter(app.world())
+            .collect();
+        for &diamond in &diamonds[1..] {
+            app.world_mut().despawn(diamond);
+        }
+        app.world_mut()
+            .entity_mut(diamonds[0])
+            .insert(Transform::from_translation(
+                hero_position + FEET_OFFSET.extend(0.0) + Vec3::Y * 16.0,
+            ));
+
+        // And an enemy on top of the hero with one life to go
+        app.world_mut().spawn((
+            Transform::from_translation(hero_position),
+            Hitbox::at_feet(Shape::Circle { radius: 10.0 }, Vec2::ZERO),
+            Enemy,
+            EnemyKind {
+                name: "crawler".to_string(),
+                score: 1,
+            },
+            OnGameScreen,
+        ));
+        app.world_mut().resource_mut::<Lives>().lose();
+        app.world_mut().resource_mut::<Lives>().lose();
+
+        app.update();
+        app.update();
+        app.world_mut().run_schedule(StateTransition);
+
+        assert_eq!(app.world().resource::<Lives>().value(), 0);
+        assert_eq!(app.world().resource::<Score>().value(), 1);
+        assert_eq!(
+            *app.world().resource::<State<InGameState>>().get(),
+            InGameState::Playing
+        );
+        assert_eq!(
+            app.world_mut()
+                .query_filtered::<(), (With<Player>, With<Dying>)>()
+                .iter(app.world())
+                .count(),
+            1
+        );
+    }
 }
@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+
+use crate::game::tilemap::TileMap;
+
+// Steps to the 8 neighbours, diagonals cost a bit more
+const NEIGHBOURS: [(IVec2, f32); 8] = [
+    (IVec2::new(1, 0), 1.0),
+    (IVec2::new(-1, 0), 1.0),
+    (IVec2::new(0, 1), 1.0),
+    (IVec2::new(0, -1), 1.0),
+    (IVec2::new(1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(1, -1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, -1), std::f32::consts::SQRT_2),
+];
+
+// Cost of walking from every tile to one goal tile, built once and shared by every enemy
+// heading there
+struct FlowField {
+    costs: Vec<f32>,
+    used: bool,
+}
+
+#[derive(PartialEq)]
+struct Step {
+    cost: f32,
+    tile: IVec2,
+}
+
+impl Eq for Step {}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the heap pops the cheapest step first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FlowField {
+    fn build(map: &TileMap, goal: IVec2) -> Self {
+        let size = map.size();
+        let mut costs = vec![f32::INFINITY; (size.x * size.y) as usize];
+        let mut open = BinaryHeap::new();
+
+        if !map.is_solid(goal) {
+            costs[index(map, goal)] = 0.0;
+            open.push(Step {
+                cost: 0.0,
+                tile: goal,
+            });
+        }
+
+        while let Some(Step { cost, tile }) = open.pop() {
+            if cost > costs[index(map, tile)] {
+                continue;
+            }
+
+            for (offset, step_cost) in NEIGHBOURS {
+                let next = tile + offset;
+                if !can_step(map, tile, offset) {
+                    continue;
+                }
+
+                let next_cost = cost + step_cost;
+                if next_cost < costs[index(map, next)] {
+                    costs[index(map, next)] = next_cost;
+                    open.push(Step {
+                        cost: next_cost,
+                        tile: next,
+                    });
+                }
+            }
+        }
+
+        FlowField { costs, used: true }
+    }
+
+    fn cost(&self, map: &TileMap, tile: IVec2) -> f32 {
+        if map.contains(tile) {
+            self.costs[index(map, tile)]
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    // The neighbour that gets closest to the goal, None when the goal can't be reached
+    fn next_tile(&self, map: &TileMap, tile: IVec2) -> Option<IVec2> {
+        NEIGHBOURS
+            .iter()
+            .filter(|(offset, _)| can_step(map, tile, *offset))
+            .map(|(offset, _)| tile + *offset)
+            .filter(|&next| self.cost(map, next) < self.cost(map, tile))
+            .min_by(|&a, &b| self.cost(map, a).total_cmp(&self.cost(map, b)))
+    }
+}
+
+fn index(map: &TileMap, tile: IVec2) -> usize {
+    (tile.y * map.size().x + tile.x) as usize
+}
+
+// Diagonal steps need both sides open, otherwise enemies would cut through wall corners
+fn can_step(map: &TileMap, tile: IVec2, offset: IVec2) -> bool {
+    !map.is_solid(tile + offset)
+        && (offset.x == 0
+            || offset.y == 0
+            || (!map.is_solid(tile + IVec2::new(offset.x, 0))
+                && !map.is_solid(tile + IVec2::new(0, offset.y))))
+}
+
+// Flow fields by goal tile. A field stays around while some enemy keeps heading for its goal,
+// so chasers only pay for a new one when the hero steps onto another tile.
+#[derive(Resource, Default)]
+pub(crate) struct Navigation {
+    fields: HashMap<IVec2, FlowField>,
+}
+
+impl Navigation {
+    // Which way to walk from one spot to reach another, both measured at the feet
+    pub fn direction(&mut self, map: &TileMap, from: Vec2, to: Vec2) -> Vec2 {
+        let start = map.tile_at(from);
+        let goal = map.tile_at(to);
+
+        // Same tile, or somewhere the grid can't help, e.g. pushed half into a wall
+        if start == goal || map.is_solid(start) || map.is_solid(goal) {
+            return (to - from).normalize_or_zero();
+        }
+
+        let field = self
+            .fields
+            .entry(goal)
+            .or_insert_with(|| FlowField::build(map, goal));
+        field.used = true;
+
+        match field.next_tile(map, start) {
+            Some(next) if next == goal => (to - from).normalize_or_zero(),
+            Some(next) => (map.tile_center(next) - from).normalize_or_zero(),
+            // Walled off from the goal, walking at it is the best there is
+            None => (to - from).normalize_or_zero(),
+        }
+    }
+}
+
+// Runs before the enemies ask for directions. Drops the fields nobody used last frame, and
+// all of them once a new level is built.
+pub(crate) fn prune_navigation(map: Res<TileMap>, mut navigation: ResMut<Navigation>) {
+    if map.is_changed() {
+        navigation.fields.clear();
+        return;
+    }
+
+    navigation.fields.retain(|_, field| field.used);
+    for field in navigation.fields.values_mut() {
+        field.used = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tilemap::LevelLayout;
+
+    // A wall down the middle with a gap along the bottom
+    fn map() -> TileMap {
+        TileMap::from_layout(&LevelLayout {
+            tile_size: 32.0,
+            tiles: ["#######", "#..#..#", "#..#..#", "#.....#", "#######"]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn next_tile_leads_around_the_wall() {
+        let map = map();
+        let start = IVec2::new(1, 3);
+        let goal = IVec2::new(5, 3);
+        let field = FlowField::build(&map, goal);
+
+        assert_eq!(field.cost(&map, goal), 0.0);
+        assert!(field.cost(&map, start).is_finite());
+
+        let mut tile = start;
+        for _ in 0..20 {
+            if tile == goal {
+                break;
+            }
+
+            let next = field.next_tile(&map, tile).expect("goal should be reachable");
+            let step = next - tile;
+            assert!(!map.is_solid(next));
+            // No cutting through wall corners
+            if step.x != 0 && step.y != 0 {
+                assert!(!map.is_solid(tile + IVec2::new(step.x, 0)));
+                assert!(!map.is_solid(tile + IVec2::new(0, step.y)));
+            }
+            tile = next;
+        }
+
+        assert_eq!(tile, goal);
+    }
+
+    #[test]
+    fn walled_off_goal_has_no_next_tile() {
+        let map = TileMap::from_layout(&LevelLayout {
+            tile_size: 32.0,
+            tiles: ["#####", "#.#.#", "#####"]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+        });
+        let field = FlowField::build(&map, IVec2::new(3, 1));
+
+        assert!(field.cost(&map, IVec2::new(1, 1)).is_infinite());
+        assert_eq!(field.next_tile(&map, IVec2::new(1, 1)), None);
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::GameState;
+use crate::InGameState;
+
+#[derive(Component)]
+pub(crate) struct OnPauseScreen;
+
+#[derive(Component)]
+pub(crate) struct OnResumeScreen;
+
+#[derive(Component)]
+pub(crate) struct ResumeCountdownText;
+
+// Short countdown between leaving the pause menu and play picking up again
+#[derive(Resource)]
+pub(crate) struct ResumeCountdown {
+    timer: Timer,
+}
+
+impl Default for ResumeCountdown {
+    fn default() -> Self {
+        ResumeCountdown {
+            timer: Timer::from_seconds(3.0, TimerMode::Once),
+        }
+    }
+}
+
+// A marker component for pause menu buttons
+#[derive(Component)]
+pub(crate) enum PauseButtonAction {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+pub(crate) fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    in_game_state: Res<State<InGameState>>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if !keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyP]) {
+        return;
+    }
+
+    match in_game_state.get() {
+        InGameState::Playing | InGameState::Resuming => next_state.set(InGameState::Paused),
+        InGameState::Paused => next_state.set(InGameState::Resuming),
+        // Don't interrupt the level transition
+        InGameState::LevelCleared => {}
+    }
+}
+
+// Nobody should die while alt-tabbed, coming back still needs a key press to resume.
+// Checks the window itself rather than the focus message, focus can also get lost while
+// a level-cleared screen is up and play would pick up again unfocused.
+pub(crate) fn pause_on_focus_lost(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    in_game_state: Res<State<InGameState>>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+
+    if !window.focused
+        && matches!(
+            in_game_state.get(),
+            InGameState::Playing | InGameState::Resuming
+        )
+    {
+        println!("Window lost focus, pausing");
+        next_state.set(InGameState::Paused);
+    }
+}
+
+pub(crate) fn setup_pause_menu(mut commands: Commands) {
+    println!("Setup pause menu");
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            GlobalZIndex(10),
+            OnPauseScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Paused"),
+                TextFont {
+                    font_size: 48.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::WHITE),
+            ));
+
+            for (label, action) in [
+                ("Resume", PauseButtonAction::Resume),
+                ("Restart", PauseButtonAction::Restart),
+                ("Quit to Menu", PauseButtonAction::QuitToMenu),
+            ] {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(180.0),
+                            height: Val::Px(40.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        BorderRadius::all(Val::Px(8.0)),
+                        BorderColor::all(Color::BLACK),
+                        BackgroundColor(Color::WHITE),
+                        TextColor(Color::BLACK),
+                        action,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(label),
+                            TextFont {
+                                font_size: 24.0,
+                                font: Default::default(),
+                                ..default()
+                            },
+                            TextColor::from(Color::BLACK),
+                        ));
+                    });
+            }
+        });
+}
+
+pub(crate) fn pause_button_system(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            &mut TextColor,
+            &PauseButtonAction,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_in_game_state: ResMut<NextState<InGameState>>,
+) {
+    for (interaction, mut color, mut border_color, mut text_color, button_action) in
+        &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+
+                match button_action {
+                    PauseButtonAction::Resume => {
+                        next_in_game_state.set(InGameState::Resuming);
+                    }
+                    PauseButtonAction::Restart => {
+                        // Re-entering InGame is an identity transition, see restart in GamePlugin
+                        next_game_state.set(GameState::InGame);
+                        next_in_game_state.set(InGameState::Playing);
+                    }
+                    PauseButtonAction::QuitToMenu => {
+                        next_game_state.set(GameState::Menu);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+            }
+            Interaction::None => {
+                *color = Color::WHITE.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::BLACK.into();
+            }
+        }
+    }
+}
+
+pub(crate) fn setup_resume_countdown(
+    mut commands: Commands,
+    mut countdown: ResMut<ResumeCountdown>,
+) {
+    countdown.timer.reset();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            GlobalZIndex(10),
+            OnResumeScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("{}", countdown.timer.duration().as_secs())),
+                TextFont {
+                    font_size: 64.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::WHITE),
+                ResumeCountdownText,
+            ));
+        });
+}
+
+pub(crate) fn resume_countdown(
+    time: Res<Time>,
+    mut countdown: ResMut<ResumeCountdown>,
+    mut text_query: Query<&mut Text, With<ResumeCountdownText>>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    countdown.timer.tick(time.delta());
+
+    if countdown.timer.is_finished() {
+        next_state.set(InGameState::Playing);
+        return;
+    }
+
+    let seconds_left = countdown.timer.remaining_secs().ceil();
+    for mut text in &mut text_query {
+        **text = format!("{}", seconds_left);
+    }
+}
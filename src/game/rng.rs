@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+// Every random roll of a run comes from here. Gameplay steps in FixedUpdate, so the same seed
+// and the same keys replay the same run. Systems that draw from it need a fixed order between
+// them.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+// A fresh seed, kept short so it's easy to type back in with --seed
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random_range(0..1_000_000))
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.rng.fill_bytes(dst)
+    }
+}
+
+// Seed from --seed or the menu field, every run starts from it instead of a fresh one
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct RunSeed(pub Option<u64>);
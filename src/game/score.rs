@@ -1,14 +1,25 @@
 use bevy::prelude::*;
 
+use crate::game::OnGameScreen;
+use crate::game::hero::{Dash, Player};
+
 #[derive(Resource)]
 pub struct Score {
     value: usize,
 }
 
 impl Score {
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
     pub fn increase(&mut self) {
         self.value += 1;
     }
+
+    pub fn add(&mut self, points: usize) {
+        self.value += points;
+    }
 }
 
 impl Default for Score {
@@ -17,20 +28,59 @@ impl Default for Score {
     }
 }
 
+const STARTING_LIVES: usize = 3;
+
+// Hits the hero can still take, the run ends when the last one is gone
+#[derive(Resource)]
+pub struct Lives {
+    value: usize,
+}
+
+impl Lives {
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    // Returns how many are left
+    pub fn lose(&mut self) -> usize {
+        self.value = self.value.saturating_sub(1);
+        self.value
+    }
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Lives {
+            value: STARTING_LIVES,
+        }
+    }
+}
+
 #[derive(Component)]
 pub(crate) struct ScoreDisplay;
 
-pub(crate) fn setup_score_ui(mut commands: Commands) {
+#[derive(Component)]
+pub(crate) struct LivesDisplay;
+
+// Fill of the dash indicator, full when the dash is ready
+#[derive(Component)]
+pub(crate) struct DashCooldownBar;
+
+pub(crate) fn setup_score_ui(mut commands: Commands, lives: Res<Lives>) {
     println!("Setup score UI");
     // Top-level node for the UI
     commands
-        .spawn((Node {
-            width: Val::Percent(100.0),
-            height: Val::Px(50.0),
-            justify_content: JustifyContent::Center, // Left align horizontally
-            align_items: AlignItems::Center,         // Top align vertically
-            ..default()
-        },))
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(50.0),
+                justify_content: JustifyContent::Center, // Left align horizontally
+                align_items: AlignItems::Center,         // Top align vertically
+                column_gap: Val::Px(10.0),
+                ..default()
+            },
+            OnGameScreen,
+        ))
         .with_children(|parent| {
             parent
                 .spawn((
@@ -58,6 +108,79 @@ pub(crate) fn setup_score_ui(mut commands: Commands) {
                         ScoreDisplay, // Attach the marker component
                     ));
                 });
+
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(80.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.)),
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.5)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(format!("lives {}", lives.value())),
+                        TextFont {
+                            font: Default::default(),
+                            font_size: 20.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::BLACK),
+                        LivesDisplay,
+                    ));
+                });
+
+            // Dash cooldown indicator
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(80.0),
+                        height: Val::Px(40.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.)),
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.5)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("dash"),
+                        TextFont {
+                            font: Default::default(),
+                            font_size: 14.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::BLACK),
+                    ));
+
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Px(60.0),
+                                height: Val::Px(6.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgb(0.2, 0.8, 0.2)),
+                                DashCooldownBar,
+                            ));
+                        });
+                });
         });
 }
 
@@ -74,3 +197,41 @@ pub(crate) fn update_score_ui(
         }
     }
 }
+
+pub(crate) fn update_lives_ui(
+    lives: Res<Lives>,
+    mut query: Query<&mut Text, With<LivesDisplay>>,
+) {
+    if !lives.is_changed() {
+        return;
+    }
+
+    for mut text in &mut query {
+        **text = format!("lives {}", lives.value());
+    }
+}
+
+pub(crate) fn update_dash_ui(
+    dash_query: Query<&Dash, With<Player>>,
+    mut bar_query: Query<(&mut Node, &mut BackgroundColor), With<DashCooldownBar>>,
+) {
+    let Ok(dash) = dash_query.single() else {
+        return;
+    };
+
+    let ready = 1.0 - dash.cooldown_fraction();
+    let width = Val::Percent(ready * 100.0);
+    let bar_color = BackgroundColor(if ready >= 1.0 {
+        Color::srgb(0.2, 0.8, 0.2)
+    } else {
+        Color::srgb(0.8, 0.6, 0.2)
+    });
+
+    // Only touch the bar while it fills, a changed Node means a new layout
+    for (mut node, mut color) in &mut bar_query {
+        if node.width != width {
+            node.width = width;
+        }
+        color.set_if_neq(bar_color);
+    }
+}
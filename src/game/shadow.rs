@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::game::enemies::Enemy;
+use crate::game::hero::{Dying, Jump, Player};
+
+// At the top of a jump the shadow shrinks and fades by this much
+const SHADOW_MIN_SCALE: f32 = 0.6;
+const SHADOW_MIN_ALPHA: f32 = 0.4;
+
+#[derive(Component)]
+pub(crate) struct Shadow;
+
+#[derive(Resource)]
+pub(crate) struct ShadowData {
+    ground: Handle<Image>,
+    jump: Handle<Image>,
+    // Jump_Shadow.png is a single row of eight frames following the jump arc
+    jump_layout: Handle<TextureAtlasLayout>,
+}
+
+pub(crate) fn setup_shadows(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    println!("Setup shadows");
+
+    let ground = asset_server.load::<Image>("sprites/characters/hero/Shadow.png");
+    let jump = asset_server.load::<Image>("sprites/characters/hero/jump/Jump_Shadow.png");
+    let jump_layout = TextureAtlasLayout::from_grid(UVec2::new(48, 64), 8, 1, None, None);
+
+    commands.insert_resource(ShadowData {
+        ground,
+        jump,
+        jump_layout: texture_atlas_layouts.add(jump_layout),
+    });
+}
+
+// Every character gets a shadow as soon as it shows up
+pub(crate) fn attach_shadows(
+    mut commands: Commands,
+    query: Query<Entity, Or<(Added<Player>, Added<Enemy>)>>,
+    shadow_data: Res<ShadowData>,
+) {
+    for entity in &query {
+        commands.entity(entity).with_child((
+            Sprite::from_image(shadow_data.ground.clone()),
+            // Just behind the character so it stays on the ground while the sprite is lifted
+            Transform::from_xyz(0.0, 0.0, -0.1),
+            Shadow,
+        ));
+    }
+}
+
+pub(crate) fn update_shadows(
+    parent_query: Query<(Option<&Jump>, Has<Dying>)>,
+    mut shadow_query: Query<(&ChildOf, &mut Sprite, &mut Transform), With<Shadow>>,
+    shadow_data: Res<ShadowData>,
+) {
+    for (child_of, mut sprite, mut transform) in &mut shadow_query {
+        let Ok((jump, dying)) = parent_query.get(child_of.parent()) else {
+            continue;
+        };
+
+        // The death shadow takes over, see update_death_shadow
+        if dying {
+            if transform.scale != Vec3::ONE {
+                transform.scale = Vec3::ONE;
+            }
+            if sprite.color != Color::WHITE {
+                sprite.color = Color::WHITE;
+            }
+            continue;
+        }
+
+        let height = jump.map_or(0.0, |jump| jump.height_fraction());
+
+        // Only touch what differs, the sprite would count as changed every frame otherwise
+        match jump {
+            Some(jump) if jump.is_airborne() => {
+                let frame = ((jump.airborne_fraction() * 8.0) as usize).min(7);
+                if sprite.image != shadow_data.jump {
+                    sprite.image = shadow_data.jump.clone();
+                }
+                let showing_frame = sprite.texture_atlas.as_ref().is_some_and(|atlas| {
+                    atlas.layout == shadow_data.jump_layout && atlas.index == frame
+                });
+                if !showing_frame {
+                    sprite.texture_atlas = Some(TextureAtlas {
+                        layout: shadow_data.jump_layout.clone(),
+                        index: frame,
+                    });
+                }
+            }
+            _ => {
+                if sprite.image != shadow_data.ground {
+                    sprite.image = shadow_data.ground.clone();
+                }
+                if sprite.texture_atlas.is_some() {
+                    sprite.texture_atlas = None;
+                }
+            }
+        }
+
+        // Only squeeze sideways, scaling the height would pull the shadow off the feet
+        let scale = 1.0 - (1.0 - SHADOW_MIN_SCALE) * height;
+        let scale = Vec3::new(scale, 1.0, 1.0);
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+        let color = Color::WHITE.with_alpha(1.0 - (1.0 - SHADOW_MIN_ALPHA) * height);
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
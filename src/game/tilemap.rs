@@ -0,0 +1,383 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::game::OnGameScreen;
+use crate::game::character::{Bounded, WorldBounds};
+use crate::game::collision::FEET_OFFSET;
+use crate::game::enemies::EnemyMovement;
+use crate::game::generator::generate_layout;
+use crate::game::level::{Level, for_level};
+use crate::game::rng::GameRng;
+use crate::load_data;
+
+const LEVELS_FILE: &str = "data/levels.ron";
+// The part of a character around its feet that can't overlap walls
+pub(crate) const FOOTPRINT_HALF_SIZE: Vec2 = Vec2::new(10.0, 6.0);
+
+// A level as written in levels.ron, see the legend at the top of that file
+#[derive(Deserialize, Clone)]
+pub(crate) struct LevelLayout {
+    pub tile_size: f32,
+    // Top row first, one character per tile
+    pub tiles: Vec<String>,
+}
+
+// Where a run's levels come from, picked in the menu or with --caves on the command line
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LevelSource {
+    // The layouts in levels.ron
+    #[default]
+    Authored,
+    // Caves generated from the run seed, each level gets its own seed counting up from it
+    Generated,
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct LevelLayouts(Vec<LevelLayout>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tile {
+    Floor,
+    Wall,
+    // Solid decor standing on the floor
+    Rock,
+    // Decor the characters walk over
+    Grass,
+}
+
+impl Tile {
+    pub fn is_solid(&self) -> bool {
+        matches!(self, Tile::Wall | Tile::Rock)
+    }
+}
+
+// The current level's tiles and spawn points. Tile coordinates count from the bottom left
+// so they grow the same way as world coordinates.
+#[derive(Resource)]
+pub(crate) struct TileMap {
+    tile_size: f32,
+    size: IVec2,
+    tiles: Vec<Tile>,
+    hero_spawn: IVec2,
+    enemy_spawns: Vec<IVec2>,
+    diamond_spawns: Vec<IVec2>,
+}
+
+// An open 640x320 field, used when there is no level file
+impl Default for TileMap {
+    fn default() -> Self {
+        TileMap::from_layout(&LevelLayout {
+            tile_size: 32.0,
+            tiles: vec![".".repeat(20); 10],
+        })
+    }
+}
+
+impl TileMap {
+    pub fn from_layout(layout: &LevelLayout) -> Self {
+        let height = layout.tiles.len();
+        let width = layout.tiles.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let mut map = TileMap {
+            tile_size: layout.tile_size,
+            size: IVec2::new(width as i32, height as i32),
+            tiles: vec![Tile::Floor; width * height],
+            hero_spawn: IVec2::new(width as i32 / 2, height as i32 / 2),
+            enemy_spawns: Vec::new(),
+            diamond_spawns: Vec::new(),
+        };
+
+        for (row, line) in layout.tiles.iter().enumerate() {
+            let y = (height - 1 - row) as i32;
+
+            for (x, character) in line.chars().enumerate() {
+                let tile = IVec2::new(x as i32, y);
+                let kind = match character {
+                    '#' => Tile::Wall,
+                    'o' => Tile::Rock,
+                    ',' => Tile::Grass,
+                    'H' => {
+                        map.hero_spawn = tile;
+                        Tile::Floor
+                    }
+                    'E' => {
+                        map.enemy_spawns.push(tile);
+                        Tile::Floor
+                    }
+                    'D' => {
+                        map.diamond_spawns.push(tile);
+                        Tile::Floor
+                    }
+                    '.' => Tile::Floor,
+                    _ => {
+                        println!("Unknown tile '{}' at {}, using floor", character, tile);
+                        Tile::Floor
+                    }
+                };
+                map.tiles[y as usize * width + x] = kind;
+            }
+        }
+
+        map
+    }
+
+    pub fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    pub fn world_size(&self) -> Vec2 {
+        self.size.as_vec2() * self.tile_size
+    }
+
+    pub fn contains(&self, tile: IVec2) -> bool {
+        tile.cmpge(IVec2::ZERO).all() && tile.cmplt(self.size).all()
+    }
+
+    pub fn get(&self, tile: IVec2) -> Option<Tile> {
+        self.contains(tile)
+            .then(|| self.tiles[(tile.y * self.size.x + tile.x) as usize])
+    }
+
+    // Outside the map counts as solid so nothing walks off the edge
+    pub fn is_solid(&self, tile: IVec2) -> bool {
+        self.get(tile).is_none_or(|tile| tile.is_solid())
+    }
+
+    pub fn tile_at(&self, position: Vec2) -> IVec2 {
+        (position / self.tile_size).floor().as_ivec2()
+    }
+
+    pub fn tile_center(&self, tile: IVec2) -> Vec2 {
+        (tile.as_vec2() + Vec2::splat(0.5)) * self.tile_size
+    }
+
+    // Where a character has to be for its feet to stand in the middle of the tile
+    pub fn character_position(&self, tile: IVec2) -> Vec2 {
+        self.tile_center(tile) - FEET_OFFSET
+    }
+
+    pub fn hero_spawn(&self) -> IVec2 {
+        self.hero_spawn
+    }
+
+    pub fn enemy_spawns(&self) -> &[IVec2] {
+        &self.enemy_spawns
+    }
+
+    pub fn diamond_spawns(&self) -> &[IVec2] {
+        &self.diamond_spawns
+    }
+
+    pub fn open_tiles(&self) -> Vec<IVec2> {
+        (0..self.size.y)
+            .flat_map(|y| (0..self.size.x).map(move |x| IVec2::new(x, y)))
+            .filter(|&tile| !self.is_solid(tile))
+            .collect()
+    }
+
+    // How far a box has to move to get out of every solid tile it overlaps
+    pub fn push_out(&self, center: Vec2, half_size: Vec2) -> Vec2 {
+        let start = center;
+        let mut center = center;
+        let half_tile = Vec2::splat(self.tile_size / 2.0);
+        let min = self.tile_at(center - half_size);
+        let max = self.tile_at(center + half_size);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let tile = IVec2::new(x, y);
+                if !self.is_solid(tile) {
+                    continue;
+                }
+
+                let tile_center = self.tile_center(tile);
+                let overlap = half_size + half_tile - (center - tile_center).abs();
+                if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                    continue;
+                }
+
+                // Out along the shallower side, unless that side is walled off too. Keeps
+                // characters sliding along a wall instead of catching on the seams between tiles
+                let away = (center - tile_center).signum();
+                let x_blocked = self.is_solid(tile + IVec2::new(away.x as i32, 0));
+                let y_blocked = self.is_solid(tile + IVec2::new(0, away.y as i32));
+                let along_x = if x_blocked != y_blocked {
+                    y_blocked
+                } else {
+                    overlap.x < overlap.y
+                };
+
+                if along_x {
+                    center.x += overlap.x * away.x;
+                } else {
+                    center.y += overlap.y * away.y;
+                }
+            }
+        }
+
+        center - start
+    }
+}
+
+// Everything the level layout spawned, replaced when the next level starts
+#[derive(Component)]
+pub(crate) struct LevelTile;
+
+pub(crate) fn load_level_layouts(mut commands: Commands) {
+    let layouts = load_data::<Vec<LevelLayout>>(LEVELS_FILE).unwrap_or_else(|error| {
+        println!("Could not load levels: {}", error);
+        Vec::new()
+    });
+
+    commands.insert_resource(LevelLayouts(layouts));
+}
+
+pub(crate) fn build_level(
+    mut commands: Commands,
+    layouts: Res<LevelLayouts>,
+    source: Res<LevelSource>,
+    game_rng: Res<GameRng>,
+    level: Res<Level>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let map = match *source {
+        LevelSource::Authored => match for_level(&layouts.0, &level) {
+            Some(layout) => TileMap::from_layout(layout),
+            None => {
+                println!("No layout for level {}, playing on an open field", level.number());
+                TileMap::default()
+            }
+        },
+        LevelSource::Generated => {
+            let seed = game_rng.seed().wrapping_add(level.number() as u64);
+            TileMap::from_layout(&generate_layout(seed))
+        }
+    };
+
+    println!("Build level {}", level.number());
+    let tile_size = map.tile_size();
+
+    let wall_mesh = meshes.add(bevy::math::primitives::Rectangle::new(tile_size, tile_size));
+    let wall_material = materials.add(ColorMaterial::from_color(Color::srgb(0.25, 0.2, 0.2)));
+    let rock_mesh = meshes.add(bevy::math::primitives::Circle::new(tile_size * 0.4));
+    let rock_material = materials.add(ColorMaterial::from_color(Color::srgb(0.45, 0.45, 0.5)));
+    let grass_mesh =
+        meshes.add(bevy::math::primitives::Ellipse::new(tile_size * 0.3, tile_size * 0.15));
+    let grass_material =
+        materials.add(ColorMaterial::from_color(Color::srgba(0.3, 0.6, 0.2, 0.8)));
+
+    for y in 0..map.size().y {
+        for x in 0..map.size().x {
+            let tile = IVec2::new(x, y);
+            let (mesh, material) = match map.get(tile) {
+                Some(Tile::Wall) => (wall_mesh.clone(), wall_material.clone()),
+                Some(Tile::Rock) => (rock_mesh.clone(), rock_material.clone()),
+                Some(Tile::Grass) => (grass_mesh.clone(), grass_material.clone()),
+                _ => continue,
+            };
+
+            commands.spawn((
+                Mesh2d(mesh),
+                MeshMaterial2d(material),
+                // Over the background, under shadows and characters
+                Transform::from_translation(map.tile_center(tile).extend(-0.5)),
+                LevelTile,
+                OnGameScreen,
+            ));
+        }
+    }
+
+    commands.insert_resource(WorldBounds {
+        min: Vec2::ZERO,
+        max: map.world_size(),
+    });
+    commands.insert_resource(map);
+}
+
+// Pushes characters out of solid tiles, enemies turn away from the wall they ran into
+pub(crate) fn collide_with_walls(
+    map: Res<TileMap>,
+    mut query: Query<(&mut Transform, Option<&mut EnemyMovement>), With<Bounded>>,
+) {
+    for (mut transform, movement) in &mut query {
+        let feet = transform.translation.truncate() + FEET_OFFSET;
+        let push = map.push_out(feet, FOOTPRINT_HALF_SIZE);
+        if push == Vec2::ZERO {
+            continue;
+        }
+
+        transform.translation += push.extend(0.0);
+
+        if let Some(mut movement) = movement {
+            if push.x != 0.0 {
+                movement.direction.x = movement.direction.x.abs() * push.x.signum();
+            }
+            if push.y != 0.0 {
+                movement.direction.y = movement.direction.y.abs() * push.y.signum();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(tiles: &[&str]) -> TileMap {
+        TileMap::from_layout(&LevelLayout {
+            tile_size: 32.0,
+            tiles: tiles.iter().map(|line| line.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn layout_rows_count_from_the_bottom() {
+        let map = map(&["#####", "#H.E#", "#.#D#", "#####"]);
+
+        assert_eq!(map.size(), IVec2::new(5, 4));
+        assert_eq!(map.hero_spawn(), IVec2::new(1, 2));
+        assert_eq!(map.enemy_spawns(), &[IVec2::new(3, 2)]);
+        assert_eq!(map.diamond_spawns(), &[IVec2::new(3, 1)]);
+        assert!(map.is_solid(IVec2::new(2, 1)));
+        assert!(!map.is_solid(IVec2::new(1, 1)));
+        // Outside the map
+        assert!(map.is_solid(IVec2::new(-1, 1)));
+        assert!(map.is_solid(IVec2::new(5, 1)));
+        assert_eq!(map.tile_at(Vec2::new(40.0, 70.0)), IVec2::new(1, 2));
+    }
+
+    #[test]
+    fn push_out_leaves_open_floor_alone() {
+        let map = map(&["#####", "#...#", "#...#", "#####"]);
+
+        assert_eq!(map.push_out(Vec2::new(80.0, 64.0), Vec2::new(10.0, 6.0)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn push_out_moves_a_box_off_the_wall() {
+        let map = map(&["#####", "#...#", "#...#", "#####"]);
+
+        // 2px into the left wall
+        let push = map.push_out(Vec2::new(40.0, 48.0), Vec2::new(10.0, 6.0));
+        assert_eq!(push, Vec2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn push_out_slides_along_a_wall_instead_of_catching_on_seams() {
+        let map = map(&["#######", "#.....#", "#.....#", "#######"]);
+
+        // Deep in the bottom wall, right on the seam between two of its tiles. The shallower
+        // way out would be sideways into the next wall tile, up is the only real way out.
+        let center = Vec2::new(64.0, 26.0);
+        let half_size = Vec2::new(10.0, 6.0);
+        let push = map.push_out(center, half_size);
+
+        assert_eq!(push.x, 0.0);
+        assert_eq!(push, Vec2::new(0.0, 12.0));
+        assert_eq!(map.push_out(center + push, half_size), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+
+use crate::game::OnGameScreen;
+use crate::game::collision::FEET_OFFSET;
+use crate::game::enemies::{EnemyArchetypes, spawn_enemy};
+use crate::game::hero::Player;
+use crate::game::level::{Level, for_level};
+use crate::game::rng::GameRng;
+use crate::game::tilemap::TileMap;
+use crate::load_data;
+
+const WAVES_FILE: &str = "data/waves.ron";
+// Enemies never appear closer to the hero than this
+const SAFE_ZONE_RADIUS: f32 = 100.0;
+// How long the spawn marker shows before the enemy arrives
+const TELEGRAPH_SECS: f32 = 1.0;
+const SPAWN_ATTEMPTS: usize = 20;
+
+#[derive(Deserialize, Clone)]
+struct Wave {
+    // Seconds after the level started
+    at_secs: f32,
+    // Archetype names from enemies.ron
+    enemies: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+struct LevelWaves {
+    waves: Vec<Wave>,
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct WaveSchedule(Vec<LevelWaves>);
+
+// Plays the current level's waves, sending them in as time goes by
+#[derive(Resource, Default)]
+pub(crate) struct WaveDirector {
+    waves: Vec<Wave>,
+    elapsed_secs: f32,
+    next_wave: usize,
+}
+
+// Shared by every spawn marker, they only differ in scale
+#[derive(Resource)]
+pub(crate) struct TelegraphData {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+// Marks where an enemy is about to show up
+#[derive(Component)]
+pub(crate) struct SpawnTelegraph {
+    archetype: String,
+    timer: Timer,
+}
+
+pub(crate) fn load_wave_schedule(mut commands: Commands) {
+    let levels = load_data::<Vec<LevelWaves>>(WAVES_FILE).unwrap_or_else(|error| {
+        println!("Could not load waves: {}", error);
+        Vec::new()
+    });
+
+    commands.insert_resource(WaveSchedule(levels));
+}
+
+pub(crate) fn setup_telegraphs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    println!("Setup telegraphs");
+
+    commands.insert_resource(TelegraphData {
+        mesh: meshes.add(bevy::math::primitives::Ellipse::new(16.0, 6.0)),
+        material: materials.add(ColorMaterial::from_color(Color::srgba(0.9, 0.1, 0.1, 0.6))),
+    });
+}
+
+pub(crate) fn start_waves(
+    mut director: ResMut<WaveDirector>,
+    schedule: Res<WaveSchedule>,
+    level: Res<Level>,
+) {
+    println!("Start waves for level {}", level.number());
+    *director = WaveDirector {
+        waves: for_level(&schedule.0, &level)
+            .map(|level_waves| level_waves.waves.clone())
+            .unwrap_or_default(),
+        ..default()
+    };
+}
+
+// Random spawn point away from the hero, the furthest candidate if none is clear of the safe
+// zone. Levels without enemy spawn points use any open tile.
+fn spawn_position(random_gen: &mut impl Rng, map: &TileMap, hero_position: Vec2) -> Vec2 {
+    let open_tiles;
+    let candidates = if map.enemy_spawns().is_empty() {
+        open_tiles = map.open_tiles();
+        &open_tiles[..]
+    } else {
+        map.enemy_spawns()
+    };
+
+    let mut best = map.character_position(map.hero_spawn());
+    let mut best_distance = -1.0;
+
+    for _ in 0..SPAWN_ATTEMPTS {
+        let Some(&tile) = candidates.choose(random_gen) else {
+            break;
+        };
+        let candidate = map.character_position(tile);
+        let distance = candidate.distance(hero_position);
+
+        if distance > SAFE_ZONE_RADIUS {
+            return candidate;
+        }
+        if distance > best_distance {
+            best = candidate;
+            best_distance = distance;
+        }
+    }
+
+    best
+}
+
+pub(crate) fn wave_director(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    hero_query: Query<&Transform, With<Player>>,
+    telegraph_data: Res<TelegraphData>,
+    map: Res<TileMap>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    director.elapsed_secs += time.delta_secs();
+
+    let Ok(hero_transform) = hero_query.single() else {
+        return;
+    };
+    let hero_position = hero_transform.translation.truncate();
+
+    while let Some(wave) = director.waves.get(director.next_wave) {
+        if wave.at_secs > director.elapsed_secs {
+            break;
+        }
+
+        for archetype in &wave.enemies {
+            let position = spawn_position(&mut *game_rng, &map, hero_position);
+
+            commands.spawn((
+                Mesh2d(telegraph_data.mesh.clone()),
+                MeshMaterial2d(telegraph_data.material.clone()),
+                // Under the enemy's feet
+                Transform::from_translation((position + FEET_OFFSET).extend(-0.2)),
+                SpawnTelegraph {
+                    archetype: archetype.clone(),
+                    timer: Timer::from_seconds(TELEGRAPH_SECS, TimerMode::Once),
+                },
+                OnGameScreen,
+            ));
+        }
+
+        director.next_wave += 1;
+    }
+}
+
+pub(crate) fn spawn_telegraphed_enemies(
+    mut commands: Commands,
+    mut telegraph_query: Query<(Entity, &mut Transform, &mut SpawnTelegraph)>,
+    hero_query: Query<&Transform, (With<Player>, Without<SpawnTelegraph>)>,
+    archetypes: Res<EnemyArchetypes>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let Ok(hero_transform) = hero_query.single() else {
+        return;
+    };
+    let hero_position = hero_transform.translation.truncate();
+
+    for (entity, mut transform, mut telegraph) in &mut telegraph_query {
+        telegraph.timer.tick(time.delta());
+
+        // Pulse faster as the spawn gets closer
+        let pulse = (telegraph.timer.elapsed_secs() * 8.0 * (1.0 + telegraph.timer.fraction()))
+            .sin()
+            .abs();
+        transform.scale = Vec3::splat(0.8 + 0.4 * pulse);
+
+        if !telegraph.timer.is_finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate() - FEET_OFFSET;
+        // Hold the spawn while the hero stands on it
+        if position.distance(hero_position) < SAFE_ZONE_RADIUS / 2.0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        match archetypes.get(&telegraph.archetype) {
+            Some(archetype) => spawn_enemy(&mut commands, archetype, position, &level),
+            None => println!("Unknown enemy type {}", telegraph.archetype),
+        }
+    }
+}
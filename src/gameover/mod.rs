@@ -1,13 +1,22 @@
 use crate::GameState;
 use crate::despawn_screen;
+use crate::game::{GameRng, Score};
+use crate::leaderboard::HighScores;
+use crate::text_field::type_into;
+use bevy::input::keyboard::KeyboardInput;
 use bevy::prelude::*;
 
+const MAX_NAME_LENGTH: usize = 12;
+
 pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::GameOver), setup_menu)
-            .add_systems(Update, button_system.run_if(in_state(GameState::GameOver)))
+            .add_systems(
+                Update,
+                (name_input_system, button_system).run_if(in_state(GameState::GameOver)),
+            )
             .add_systems(OnExit(GameState::GameOver), despawn_screen::<OnGameOverScreen>);
     }
 }
@@ -15,14 +24,30 @@ impl Plugin for GameOverPlugin {
 #[derive(Component)]
 struct OnGameOverScreen;
 
+// Text field for the player name, only spawned when the score makes the table
+#[derive(Component, Default)]
+struct NameInput {
+    value: String,
+}
+
+#[derive(Component)]
+struct NameInputError;
+
 // A marker component for menu buttons
 #[derive(Component)]
 enum MenuButtonAction {
     Ok,
 }
 
-fn setup_menu(mut commands: Commands) {
+fn setup_menu(
+    mut commands: Commands,
+    score: Res<Score>,
+    game_rng: Res<GameRng>,
+    high_scores: Res<HighScores>,
+) {
     println!("Setup menu");
+    let is_high_score = high_scores.qualifies(score.value());
+
     let camera = Camera2d::default();
 
     commands.spawn((
@@ -69,6 +94,77 @@ fn setup_menu(mut commands: Commands) {
                     TextColor::from(Color::BLACK),
                 ));
 
+            parent.spawn((
+                Text::new(format!("score {}", score.value())),
+                TextFont {
+                    font_size: 24.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::BLACK),
+            ));
+
+            // Same levels, diamonds and enemy rolls again with --seed
+            parent.spawn((
+                Text::new(format!("seed {}", game_rng.seed())),
+                TextFont {
+                    font_size: 14.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::srgb(0.4, 0.4, 0.4)),
+            ));
+
+            if is_high_score {
+                parent.spawn((
+                    Text::new("New high score! Enter your name:"),
+                    TextFont {
+                        font_size: 18.0,
+                        font: Default::default(),
+                        ..default()
+                    },
+                    TextColor::from(Color::BLACK),
+                ));
+
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Px(220.0),
+                            height: Val::Px(36.0),
+                            padding: UiRect::horizontal(Val::Px(8.0)),
+                            align_items: AlignItems::Center,
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        BorderRadius::all(Val::Px(4.0)),
+                        BorderColor::all(Color::BLACK),
+                        BackgroundColor(Color::WHITE),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new("_"),
+                            TextFont {
+                                font_size: 20.0,
+                                font: Default::default(),
+                                ..default()
+                            },
+                            TextColor::from(Color::BLACK),
+                            NameInput::default(),
+                        ));
+                    });
+
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: 14.0,
+                        font: Default::default(),
+                        ..default()
+                    },
+                    TextColor::from(Color::srgb(0.8, 0.0, 0.0)),
+                    NameInputError,
+                ));
+            }
+
             parent
                 .spawn((
                     Button,
@@ -97,11 +193,49 @@ fn setup_menu(mut commands: Commands) {
                         TextColor::from(Color::BLACK),
                     ));
                 });
-
-            
         });
 }
 
+fn name_input_system(
+    mut keyboard_events: MessageReader<KeyboardInput>,
+    mut input_query: Query<(&mut NameInput, &mut Text)>,
+) {
+    let Ok((mut name_input, mut text)) = input_query.single_mut() else {
+        keyboard_events.clear();
+        return;
+    };
+
+    if type_into(
+        &mut keyboard_events,
+        &mut name_input.value,
+        MAX_NAME_LENGTH,
+        |character| character.is_alphanumeric() || character == ' ',
+    ) {
+        **text = format!("{}_", name_input.value);
+    }
+}
+
+// Save the typed name with the score, false if the name is not valid yet
+fn submit_name(
+    name_input: &NameInput,
+    score: &Score,
+    high_scores: &mut HighScores,
+    error_text: &mut Text,
+) -> bool {
+    let name = name_input.value.trim();
+
+    if name.is_empty() {
+        **error_text = "Please enter a name".into();
+        return false;
+    }
+
+    if high_scores.insert(name.to_string(), score.value()).is_some() {
+        high_scores.save();
+    }
+
+    true
+}
+
 fn button_system(
     mut interaction_query: Query<
         (
@@ -113,6 +247,10 @@ fn button_system(
         ),
         (Changed<Interaction>, With<Button>),
     >,
+    name_input_query: Query<&NameInput>,
+    mut error_query: Query<&mut Text, With<NameInputError>>,
+    score: Res<Score>,
+    mut high_scores: ResMut<HighScores>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     //println!("Handle buttons");
@@ -128,6 +266,13 @@ fn button_system(
                 // Match on the button's action to trigger the correct event
                 match menu_button_action {
                     MenuButtonAction::Ok => {
+                        if let (Ok(name_input), Ok(mut error_text)) =
+                            (name_input_query.single(), error_query.single_mut())
+                            && !submit_name(name_input, &score, &mut high_scores, &mut error_text)
+                        {
+                            continue;
+                        }
+
                         next_state.set(GameState::Menu);
                     }
                 }
@@ -0,0 +1,241 @@
+use crate::GameState;
+use crate::despawn_screen;
+use crate::storage;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+const MAX_HIGH_SCORES: usize = 10;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_systems(OnEnter(GameState::Leaderboard), setup_leaderboard)
+            .add_systems(Update, button_system.run_if(in_state(GameState::Leaderboard)))
+            .add_systems(OnExit(GameState::Leaderboard), despawn_screen::<OnLeaderboardScreen>);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: usize,
+}
+
+// Top scores, best first, persisted in the user data dir
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        storage::load(HIGH_SCORES_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::save(HIGH_SCORES_FILE, self) {
+            println!("Could not save high scores: {}", error);
+        }
+    }
+
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // Returns the rank the score landed on, if it made the table
+    pub fn insert(&mut self, name: String, score: usize) -> Option<usize> {
+        if !self.qualifies(score) {
+            return None;
+        }
+
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(position, HighScoreEntry { name, score });
+        self.entries.truncate(MAX_HIGH_SCORES);
+
+        Some(position)
+    }
+}
+
+#[derive(Component)]
+struct OnLeaderboardScreen;
+
+// A marker component for leaderboard buttons
+#[derive(Component)]
+enum LeaderboardButtonAction {
+    Back,
+}
+
+fn setup_leaderboard(mut commands: Commands, high_scores: Res<HighScores>) {
+    println!("Setup leaderboard");
+    let camera = Camera2d::default();
+
+    commands.spawn((
+        camera,
+        Transform {
+            translation: Vec3::new(640.0 / 2.0, 320.0 / 2.0, 0.0),
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: bevy::camera::ScalingMode::AutoMax {
+                max_width: (640.0),
+                max_height: (320.0),
+            },
+            scale: 1.0,
+            ..OrthographicProjection::default_2d()
+        }),
+        OnLeaderboardScreen,
+    ));
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+            OnLeaderboardScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Leaderboard"),
+                TextFont {
+                    font_size: 32.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::BLACK),
+            ));
+
+            if high_scores.entries().is_empty() {
+                parent.spawn((
+                    Text::new("No scores yet"),
+                    TextFont {
+                        font_size: 18.0,
+                        font: Default::default(),
+                        ..default()
+                    },
+                    TextColor::from(Color::BLACK),
+                ));
+            }
+
+            for (rank, entry) in high_scores.entries().iter().enumerate() {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (text, width) in [
+                            (format!("{}.", rank + 1), 40.0),
+                            (entry.name.clone(), 160.0),
+                            (entry.score.to_string(), 60.0),
+                        ] {
+                            parent.spawn((
+                                Node {
+                                    width: Val::Px(width),
+                                    ..default()
+                                },
+                                Text::new(text),
+                                TextFont {
+                                    font_size: 16.0,
+                                    font: Default::default(),
+                                    ..default()
+                                },
+                                TextColor::from(Color::BLACK),
+                            ));
+                        }
+                    });
+            }
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(150.0),
+                        height: Val::Px(40.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor::all(Color::BLACK),
+                    BackgroundColor(Color::WHITE),
+                    TextColor(Color::BLACK),
+                    LeaderboardButtonAction::Back,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Back"),
+                        TextFont {
+                            font_size: 24.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+                });
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            &mut TextColor,
+            &LeaderboardButtonAction,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, mut border_color, mut text_color, button_action) in
+        &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+
+                match button_action {
+                    LeaderboardButtonAction::Back => {
+                        next_state.set(GameState::Menu);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+            }
+            Interaction::None => {
+                *color = Color::WHITE.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::BLACK.into();
+            }
+        }
+    }
+}
@@ -2,8 +2,15 @@ pub mod game;
 pub mod menu;
 pub mod splash;
 pub mod gameover;
+pub mod credits;
+pub mod leaderboard;
+pub mod settings;
+pub mod storage;
+pub mod text_field;
 
+use bevy::asset::io::file::FileAssetReader;
 use bevy::prelude::*;
+use serde::de::DeserializeOwned;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
@@ -17,6 +24,18 @@ pub enum GameState {
     Settings,
 }
 
+// Phases of a run while the game screen is up
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(GameState = GameState::InGame)]
+pub enum InGameState {
+    #[default]
+    Playing,
+    LevelCleared,
+    Paused,
+    // Counting down back into Playing after a pause
+    Resuming,
+}
+
 // Generic despawn system for cleanup
 pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
     //println!("Despawn screen: {:?}", &query);
@@ -24,3 +43,12 @@ pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity,
         commands.entity(entity).despawn();
     }
 }
+
+// Read a RON data file that ships in the assets folder
+pub fn load_data<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let path = FileAssetReader::get_base_path().join("assets").join(path);
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+
+    ron::from_str(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+}
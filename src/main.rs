@@ -1,14 +1,18 @@
 use bevy::window::WindowResolution;
 use bevy::{prelude::*, winit::WinitSettings};
-use bevy_simple_text_input::TextInputPlugin;
 
 use diamond_dash::GameState;
-use diamond_dash::game::GamePlugin;
+use diamond_dash::game::{GamePlugin, LevelSource, RunSeed};
 use diamond_dash::menu::MenuPlugin;
 use diamond_dash::splash::SplashPlugin;
 use diamond_dash::gameover::GameOverPlugin;
+use diamond_dash::leaderboard::LeaderboardPlugin;
+use diamond_dash::credits::CreditsPlugin;
+use diamond_dash::settings::SettingsPlugin;
 
 fn main() {
+    let (level_source, run_seed) = parse_args();
+
     App::new()
         .add_plugins(
             DefaultPlugins
@@ -23,13 +27,38 @@ fn main() {
                 })
                 .set(ImagePlugin::default_nearest()),
         )
-        //.add_plugins(TextInputPlugin)
         .init_state::<GameState>()
         .insert_resource(WinitSettings::game())
         //.init_resource::<CharacterCreationData>()
         .add_plugins(SplashPlugin)
         .add_plugins(MenuPlugin)
         .add_plugins(GamePlugin)
+        .insert_resource(level_source)
+        .insert_resource(run_seed)
         .add_plugins(GameOverPlugin)
+        .add_plugins(LeaderboardPlugin)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(CreditsPlugin)
         .run();
 }
+
+// `--seed <number>` replays every run from that seed, `--caves` plays generated caves
+// instead of the authored levels
+fn parse_args() -> (LevelSource, RunSeed) {
+    let mut level_source = LevelSource::Authored;
+    let mut run_seed = RunSeed(None);
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--caves" => level_source = LevelSource::Generated,
+            "--seed" => match args.next().map(|value| value.parse::<u64>()) {
+                Some(Ok(seed)) => run_seed = RunSeed(Some(seed)),
+                _ => println!("--seed needs a number, using a fresh seed every run"),
+            },
+            _ => println!("Unknown argument {}", arg),
+        }
+    }
+
+    (level_source, run_seed)
+}
@@ -1,13 +1,23 @@
 use crate::GameState;
 use crate::despawn_screen;
+use crate::game::{LevelSource, RunSeed};
+use crate::text_field::type_into;
+use bevy::input::keyboard::KeyboardInput;
 use bevy::prelude::*;
 
+// Long enough for any seed the game picks on its own
+const MAX_SEED_LENGTH: usize = 9;
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, button_system.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                (button_system, update_level_source_label, seed_input_system)
+                    .run_if(in_state(GameState::Menu)),
+            )
             .add_systems(OnExit(GameState::Menu), despawn_screen::<OnMenuScreen>);
     }
 }
@@ -19,14 +29,43 @@ struct OnMenuScreen;
 #[derive(Component)]
 enum MenuButtonAction {
     NewGame,
+    CycleLevelSource,
     Leaderboard,
     Credits,
     Settings,
     Quit,
 }
 
-fn setup_menu(mut commands: Commands) {
+// Text of the button that switches between the authored levels and generated caves
+#[derive(Component)]
+struct LevelSourceLabel;
+
+fn level_source_text(source: &LevelSource) -> &'static str {
+    match source {
+        LevelSource::Authored => "Maps: Levels",
+        LevelSource::Generated => "Maps: Caves",
+    }
+}
+
+// Seed for the next runs, typed as digits. Left empty every run gets a fresh seed.
+#[derive(Component, Default)]
+struct SeedInput {
+    value: String,
+}
+
+fn seed_input_text(seed_input: &SeedInput) -> String {
+    if seed_input.value.is_empty() {
+        "Seed: random".into()
+    } else {
+        format!("Seed: {}_", seed_input.value)
+    }
+}
+
+fn setup_menu(mut commands: Commands, level_source: Res<LevelSource>, run_seed: Res<RunSeed>) {
     println!("Setup menu");
+    let seed_input = SeedInput {
+        value: run_seed.0.map(|seed| seed.to_string()).unwrap_or_default(),
+    };
     let camera = Camera2d::default();
 
     commands.spawn((
@@ -90,6 +129,159 @@ fn setup_menu(mut commands: Commands) {
                     ));
                 });
 
+            // Maps and seed share a row, the column is already as tall as the screen
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(150.0),
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(8.0)),
+                            BorderColor::all(Color::BLACK),
+                            BackgroundColor(Color::WHITE),
+                            TextColor(Color::BLACK),
+                            MenuButtonAction::CycleLevelSource,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(level_source_text(&level_source)),
+                                TextFont {
+                                    font_size: 24.0,
+                                    font: Default::default(),
+                                    ..default()
+                                },
+                                TextColor::from(Color::BLACK),
+                                LevelSourceLabel,
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Px(150.0),
+                                height: Val::Px(40.0),
+                                padding: UiRect::horizontal(Val::Px(8.0)),
+                                align_items: AlignItems::Center,
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(4.0)),
+                            BorderColor::all(Color::BLACK),
+                            BackgroundColor(Color::WHITE),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(seed_input_text(&seed_input)),
+                                TextFont {
+                                    font_size: 18.0,
+                                    font: Default::default(),
+                                    ..default()
+                                },
+                                TextColor::from(Color::BLACK),
+                                seed_input,
+                            ));
+                        });
+                });
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(150.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor::all(Color::BLACK),
+                    BackgroundColor(Color::WHITE),
+                    TextColor(Color::BLACK),
+                    MenuButtonAction::Leaderboard,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Leaderboard"),
+                        TextFont {
+                            font_size: 24.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+                });
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(150.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor::all(Color::BLACK),
+                    BackgroundColor(Color::WHITE),
+                    TextColor(Color::BLACK),
+                    MenuButtonAction::Settings,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Settings"),
+                        TextFont {
+                            font_size: 24.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+                });
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(150.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor::all(Color::BLACK),
+                    BackgroundColor(Color::WHITE),
+                    TextColor(Color::BLACK),
+                    MenuButtonAction::Credits,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Credits"),
+                        TextFont {
+                            font_size: 24.0,
+                            font: Default::default(),
+                            ..default()
+                        },
+                        TextColor::from(Color::BLACK),
+                    ));
+                });
+
             parent
                 .spawn((
                     Button,
@@ -134,6 +326,7 @@ fn button_system(
     >,
     mut exit: MessageWriter<AppExit>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut level_source: ResMut<LevelSource>,
 ) {
     //println!("Handle buttons");
     for (interaction, mut color, mut border_color, mut text_color, menu_button_action) in
@@ -151,9 +344,16 @@ fn button_system(
                         println!("Starting a new game!");
                         next_state.set(GameState::InGame);
                     }
+                    MenuButtonAction::CycleLevelSource => {
+                        *level_source = match *level_source {
+                            LevelSource::Authored => LevelSource::Generated,
+                            LevelSource::Generated => LevelSource::Authored,
+                        };
+                        println!("Playing {:?}", *level_source);
+                    }
                     MenuButtonAction::Settings => {
                         println!("Opening the settings menu.");
-                        // Transition to a settings state or open a menu popup
+                        next_state.set(GameState::Settings);
                     }
                     MenuButtonAction::Quit => {
                         println!("Quitting the game.");
@@ -161,9 +361,11 @@ fn button_system(
                     }
                     MenuButtonAction::Credits => {
                         println!("Show credits.");
+                        next_state.set(GameState::Credits);
                     }
                     MenuButtonAction::Leaderboard => {
                         println!("Show leaderboard.");
+                        next_state.set(GameState::Leaderboard);
                     }
                 }
             }
@@ -180,3 +382,38 @@ fn button_system(
         }
     }
 }
+
+fn update_level_source_label(
+    level_source: Res<LevelSource>,
+    mut query: Query<&mut Text, With<LevelSourceLabel>>,
+) {
+    if !level_source.is_changed() {
+        return;
+    }
+
+    for mut text in &mut query {
+        **text = level_source_text(&level_source).into();
+    }
+}
+
+fn seed_input_system(
+    mut keyboard_events: MessageReader<KeyboardInput>,
+    mut input_query: Query<(&mut SeedInput, &mut Text)>,
+    mut run_seed: ResMut<RunSeed>,
+) {
+    let Ok((mut seed_input, mut text)) = input_query.single_mut() else {
+        keyboard_events.clear();
+        return;
+    };
+
+    if type_into(
+        &mut keyboard_events,
+        &mut seed_input.value,
+        MAX_SEED_LENGTH,
+        |character| character.is_ascii_digit(),
+    ) {
+        **text = seed_input_text(&seed_input);
+        // The reset at the start of the next run picks this up
+        run_seed.0 = seed_input.value.parse().ok();
+    }
+}
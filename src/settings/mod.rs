@@ -0,0 +1,366 @@
+use crate::GameState;
+use crate::despawn_screen;
+use crate::storage;
+use bevy::audio::{GlobalVolume, Volume};
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_systems(Update, apply_settings.run_if(resource_changed::<Settings>))
+            .add_systems(OnEnter(GameState::Settings), setup_settings)
+            .add_systems(
+                Update,
+                (button_system, update_setting_labels).run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(
+                OnExit(GameState::Settings),
+                despawn_screen::<OnSettingsScreen>,
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedPreset {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl SpeedPreset {
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            SpeedPreset::Slow => 0.75,
+            SpeedPreset::Normal => 1.0,
+            SpeedPreset::Fast => 1.25,
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            SpeedPreset::Slow => SpeedPreset::Normal,
+            SpeedPreset::Normal => SpeedPreset::Fast,
+            SpeedPreset::Fast => SpeedPreset::Slow,
+        }
+    }
+}
+
+// Player options, loaded at startup and written back whenever they change
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    // 0 to 100 percent
+    pub volume: u32,
+    pub fullscreen: bool,
+    // Multiplier of the 640x320 play area
+    pub window_scale: u32,
+    pub movement_speed: SpeedPreset,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 50,
+            fullscreen: false,
+            window_scale: 1,
+            movement_speed: SpeedPreset::Normal,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::save(SETTINGS_FILE, self) {
+            println!("Could not save settings: {}", error);
+        }
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    global_volume.volume = Volume::Linear(settings.volume as f32 / 100.0);
+
+    if let Ok(mut window) = window_query.single_mut() {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+
+        let scale = settings.window_scale.clamp(1, 3) as f32;
+        window.resolution.set(640.0 * scale, 320.0 * scale);
+    }
+}
+
+#[derive(Component)]
+struct OnSettingsScreen;
+
+// A marker component for settings buttons
+#[derive(Component)]
+enum SettingsButtonAction {
+    VolumeDown,
+    VolumeUp,
+    ToggleFullscreen,
+    CycleWindowScale,
+    CycleMovementSpeed,
+    Back,
+}
+
+// Text that shows the current value of a setting
+#[derive(Component)]
+enum SettingLabel {
+    Volume,
+    Display,
+    WindowScale,
+    MovementSpeed,
+}
+
+impl SettingLabel {
+    fn text(&self, settings: &Settings) -> String {
+        match self {
+            SettingLabel::Volume => format!("Volume {}%", settings.volume),
+            SettingLabel::Display => {
+                if settings.fullscreen {
+                    "Fullscreen".into()
+                } else {
+                    "Windowed".into()
+                }
+            }
+            SettingLabel::WindowScale => format!("Window scale {}x", settings.window_scale),
+            SettingLabel::MovementSpeed => format!("Speed {:?}", settings.movement_speed),
+        }
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildSpawnerCommands,
+    label: &str,
+    width: f32,
+    action: SettingsButtonAction,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(32.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BorderRadius::all(Val::Px(8.0)),
+            BorderColor::all(Color::BLACK),
+            BackgroundColor(Color::WHITE),
+            TextColor(Color::BLACK),
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 18.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::BLACK),
+            ));
+        });
+}
+
+fn spawn_label(parent: &mut ChildSpawnerCommands, settings: &Settings, label: SettingLabel) {
+    parent.spawn((
+        Node {
+            width: Val::Px(180.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Text::new(label.text(settings)),
+        TextFont {
+            font_size: 18.0,
+            font: Default::default(),
+            ..default()
+        },
+        TextColor::from(Color::BLACK),
+        label,
+    ));
+}
+
+fn setup_settings(mut commands: Commands, settings: Res<Settings>) {
+    println!("Setup settings");
+    let camera = Camera2d::default();
+
+    commands.spawn((
+        camera,
+        Transform {
+            translation: Vec3::new(640.0 / 2.0, 320.0 / 2.0, 0.0),
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: bevy::camera::ScalingMode::AutoMax {
+                max_width: (640.0),
+                max_height: (320.0),
+            },
+            scale: 1.0,
+            ..OrthographicProjection::default_2d()
+        }),
+        OnSettingsScreen,
+    ));
+
+    let row = Node {
+        flex_direction: FlexDirection::Row,
+        column_gap: Val::Px(10.0),
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+            OnSettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Settings"),
+                TextFont {
+                    font_size: 32.0,
+                    font: Default::default(),
+                    ..default()
+                },
+                TextColor::from(Color::BLACK),
+            ));
+
+            parent.spawn(row.clone()).with_children(|parent| {
+                spawn_button(parent, "-", 40.0, SettingsButtonAction::VolumeDown);
+                spawn_label(parent, &settings, SettingLabel::Volume);
+                spawn_button(parent, "+", 40.0, SettingsButtonAction::VolumeUp);
+            });
+
+            parent.spawn(row.clone()).with_children(|parent| {
+                spawn_label(parent, &settings, SettingLabel::Display);
+                spawn_button(
+                    parent,
+                    "Toggle",
+                    90.0,
+                    SettingsButtonAction::ToggleFullscreen,
+                );
+            });
+
+            parent.spawn(row.clone()).with_children(|parent| {
+                spawn_label(parent, &settings, SettingLabel::WindowScale);
+                spawn_button(
+                    parent,
+                    "Change",
+                    90.0,
+                    SettingsButtonAction::CycleWindowScale,
+                );
+            });
+
+            parent.spawn(row).with_children(|parent| {
+                spawn_label(parent, &settings, SettingLabel::MovementSpeed);
+                spawn_button(
+                    parent,
+                    "Change",
+                    90.0,
+                    SettingsButtonAction::CycleMovementSpeed,
+                );
+            });
+
+            spawn_button(parent, "Back", 150.0, SettingsButtonAction::Back);
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            &mut TextColor,
+            &SettingsButtonAction,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, mut border_color, mut text_color, button_action) in
+        &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+
+                match button_action {
+                    SettingsButtonAction::VolumeDown => {
+                        settings.volume = settings.volume.saturating_sub(10);
+                    }
+                    SettingsButtonAction::VolumeUp => {
+                        settings.volume = (settings.volume + 10).min(100);
+                    }
+                    SettingsButtonAction::ToggleFullscreen => {
+                        settings.fullscreen = !settings.fullscreen;
+                    }
+                    SettingsButtonAction::CycleWindowScale => {
+                        settings.window_scale = settings.window_scale % 3 + 1;
+                    }
+                    SettingsButtonAction::CycleMovementSpeed => {
+                        settings.movement_speed = settings.movement_speed.next();
+                    }
+                    SettingsButtonAction::Back => {
+                        next_state.set(GameState::Menu);
+                        continue;
+                    }
+                }
+
+                settings.save();
+            }
+            Interaction::Hovered => {
+                *color = Color::BLACK.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::WHITE.into();
+            }
+            Interaction::None => {
+                *color = Color::WHITE.into();
+                border_color.set_all(Color::BLACK);
+                *text_color = Color::BLACK.into();
+            }
+        }
+    }
+}
+
+fn update_setting_labels(
+    settings: Res<Settings>,
+    mut label_query: Query<(&SettingLabel, &mut Text)>,
+) {
+    if settings.is_changed() {
+        for (label, mut text) in &mut label_query {
+            **text = label.text(&settings);
+        }
+    }
+}
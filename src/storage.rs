@@ -0,0 +1,50 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+// Folder in the user data dir where everything we keep between runs lives
+pub fn data_dir() -> PathBuf {
+    let base = if let Ok(dir) = env::var("XDG_DATA_HOME") {
+        PathBuf::from(dir)
+    } else if let Ok(dir) = env::var("APPDATA") {
+        PathBuf::from(dir)
+    } else if let Ok(home) = env::var("HOME") {
+        if cfg!(target_os = "macos") {
+            PathBuf::from(home).join("Library/Application Support")
+        } else {
+            PathBuf::from(home).join(".local/share")
+        }
+    } else {
+        PathBuf::from(".")
+    };
+
+    base.join("diamond-dash")
+}
+
+// Read a RON file from the data dir, None if it is missing or broken
+pub fn load<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let path = data_dir().join(file_name);
+    let contents = fs::read_to_string(&path).ok()?;
+
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            println!("Could not parse {}: {}", path.display(), error);
+            None
+        }
+    }
+}
+
+// Write a value as RON into the data dir, creating the folder if needed
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+
+    fs::write(dir.join(file_name), contents).map_err(|error| error.to_string())
+}
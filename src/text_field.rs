@@ -0,0 +1,43 @@
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+// Typing for the name and seed fields. A small one of our own, bevy_simple_text_input 0.11
+// is built against bevy 0.16.
+
+// Applies this frame's key presses to a typed value: Backspace removes the last character,
+// anything `accepts` lets through is added until the value is `max_length` long.
+// Returns whether the value changed.
+pub fn type_into(
+    keyboard_events: &mut MessageReader<KeyboardInput>,
+    value: &mut String,
+    max_length: usize,
+    accepts: impl Fn(char) -> bool,
+) -> bool {
+    let mut changed = false;
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        let characters = match &event.logical_key {
+            Key::Backspace => {
+                changed |= value.pop().is_some();
+                continue;
+            }
+            Key::Character(characters) => characters.as_str(),
+            Key::Space => " ",
+            _ => continue,
+        };
+
+        for character in characters.chars() {
+            if accepts(character) && value.chars().count() < max_length {
+                value.push(character);
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::state::state::StateTransition;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::PrimaryWindow;
+
+use diamond_dash::GameState;
+use diamond_dash::game::{GameRng, GamePlugin, Level, Lives, RunSeed, Score};
+use diamond_dash::settings::Settings;
+
+// Headless app with just enough of the engine for the game plugin
+fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        StatesPlugin,
+        bevy::input::InputPlugin,
+    ))
+    .init_asset::<Image>()
+    .init_asset::<TextureAtlasLayout>()
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .insert_resource(Settings::default())
+    .init_state::<GameState>()
+    .add_plugins(GamePlugin);
+
+    app.world_mut().spawn((Window::default(), PrimaryWindow));
+    app.world_mut().run_schedule(StateTransition);
+    app
+}
+
+fn set_state(app: &mut App, state: GameState) {
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(state);
+    app.world_mut().run_schedule(StateTransition);
+}
+
+fn entity_count(app: &mut App) -> usize {
+    app.world_mut()
+        .query::<Entity>()
+        .iter(app.world())
+        .count()
+}
+
+#[test]
+fn two_runs_back_to_back_start_fresh() {
+    let mut app = test_app();
+    set_state(&mut app, GameState::Menu);
+    let entities_outside_run = entity_count(&mut app);
+
+    for _run in 0..2 {
+        set_state(&mut app, GameState::InGame);
+
+        assert_eq!(app.world().resource::<Score>().value(), 0);
+        assert_eq!(app.world().resource::<Level>().number(), 1);
+        assert_eq!(
+            app.world().resource::<Lives>().value(),
+            Lives::default().value()
+        );
+        assert!(entity_count(&mut app) > entities_outside_run);
+
+        // Play a bit and pretend the run went well
+        for _frame in 0..5 {
+            app.update();
+        }
+        app.world_mut().resource_mut::<Score>().increase();
+        app.world_mut().resource_mut::<Level>().advance();
+        app.world_mut().resource_mut::<Lives>().lose();
+
+        set_state(&mut app, GameState::GameOver);
+        assert_eq!(entity_count(&mut app), entities_outside_run);
+
+        set_state(&mut app, GameState::Menu);
+    }
+}
+
+#[test]
+fn fixed_seed_replays_the_same_run() {
+    let mut app = test_app();
+    app.insert_resource(RunSeed(Some(42)));
+    set_state(&mut app, GameState::Menu);
+
+    let mut layouts = Vec::new();
+    for _run in 0..2 {
+        set_state(&mut app, GameState::InGame);
+        assert_eq!(app.world().resource::<GameRng>().seed(), 42);
+
+        // Diamonds and level tiles, where they landed is down to the seed
+        let mut positions: Vec<_> = app
+            .world_mut()
+            .query_filtered::<&Transform, With<Mesh2d>>()
+            .iter(app.world())
+            .map(|transform| transform.translation.to_array())
+            .collect();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        layouts.push(positions);
+
+        set_state(&mut app, GameState::GameOver);
+        set_state(&mut app, GameState::Menu);
+    }
+
+    assert_eq!(layouts[0], layouts[1]);
+}
+
+// Where every character stood after each fixed step
+#[derive(Resource, Default)]
+struct StepLog(Vec<Vec<[f32; 3]>>);
+
+fn log_step(
+    mut log: ResMut<StepLog>,
+    // Hero and enemies, shadows and effects follow them anyway
+    query: Query<&Transform, (With<Sprite>, Without<ChildOf>)>,
+) {
+    let mut positions: Vec<_> = query
+        .iter()
+        .map(|transform| transform.translation.to_array())
+        .collect();
+    positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    log.0.push(positions);
+}
+
+// Long enough for the first wave to arrive and start wandering
+const STEPS: usize = 160;
+
+fn play_seeded_run(seed: u64, frame_time: Option<Duration>) -> Vec<Vec<[f32; 3]>> {
+    let mut app = test_app();
+    app.insert_resource(RunSeed(Some(seed)))
+        .init_resource::<StepLog>()
+        .add_systems(FixedPostUpdate, log_step);
+    if let Some(frame_time) = frame_time {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(frame_time));
+    }
+    set_state(&mut app, GameState::Menu);
+    set_state(&mut app, GameState::InGame);
+
+    while app.world().resource::<StepLog>().0.len() < STEPS {
+        app.update();
+    }
+
+    assert_eq!(
+        *app.world().resource::<State<GameState>>().get(),
+        GameState::InGame
+    );
+    let mut log = std::mem::take(&mut app.world_mut().resource_mut::<StepLog>().0);
+    log.truncate(STEPS);
+    assert!(log[STEPS - 1].len() > log[0].len(), "no enemies showed up");
+    log
+}
+
+#[test]
+fn fixed_seed_replays_the_enemies_whatever_the_frame_times() {
+    // Real frame times against short, even ones
+    let real_frames = play_seeded_run(7, None);
+    let short_frames = play_seeded_run(7, Some(Duration::from_millis(5)));
+
+    for (step, (real, short)) in real_frames.iter().zip(&short_frames).enumerate() {
+        assert_eq!(real, short, "runs went apart at step {}", step);
+    }
+}